@@ -1,4 +1,3 @@
-use rand;
 use std::env;
 
 fn main() {
@@ -0,0 +1,10 @@
+-- Nicknames that count as a correct guess for a user in guessquote
+CREATE TABLE IF NOT EXISTS wdl_database.user_aliases (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    alias VARCHAR(100) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_520_ci NOT NULL,
+    created_by BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_alias (alias),
+    INDEX idx_alias_user_id (user_id)
+);
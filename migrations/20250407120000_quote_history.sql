@@ -0,0 +1,35 @@
+-- One row per guessquote round
+CREATE TABLE IF NOT EXISTS wdl_database.quote_rounds (
+    id CHAR(36) PRIMARY KEY,
+    channel_id BIGINT NOT NULL,
+    started_by BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    quoted_user_id BIGINT NOT NULL,
+    mode VARCHAR(20) NOT NULL,
+    duration_secs INT NOT NULL,
+    min_length INT NOT NULL,
+    hardcore BOOLEAN NOT NULL DEFAULT FALSE,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_at TIMESTAMP NULL,
+    cancelled BOOLEAN NOT NULL DEFAULT FALSE,
+    INDEX idx_rounds_message_id (message_id),
+    INDEX idx_rounds_quoted_user_id (quoted_user_id),
+    CONSTRAINT fk_rounds_message_id FOREIGN KEY (message_id) REFERENCES discord_messages(Id)
+);
+
+-- Every guess made in a round, including wrong ones
+CREATE TABLE IF NOT EXISTS wdl_database.quote_guesses (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    round_id CHAR(36) NOT NULL,
+    message_id BIGINT NOT NULL,
+    guesser_id BIGINT NOT NULL,
+    guessed_user_id BIGINT NULL,
+    correct BOOLEAN NOT NULL,
+    latency_ms INT NOT NULL,
+    points_awarded INT NOT NULL DEFAULT 0,
+    guessed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_guesses_guesser_id (guesser_id),
+    INDEX idx_guesses_message_id (message_id),
+    CONSTRAINT fk_guesses_round_id FOREIGN KEY (round_id) REFERENCES quote_rounds(id),
+    CONSTRAINT fk_guesses_message_id FOREIGN KEY (message_id) REFERENCES discord_messages(Id)
+);
@@ -0,0 +1,26 @@
+-- Guessquote seasons, only the newest season without ended_at is active
+CREATE TABLE IF NOT EXISTS wdl_database.quote_seasons (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(100) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_520_ci NOT NULL,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_at TIMESTAMP NULL,
+    champion_id BIGINT NULL,
+    closed_by BIGINT NULL
+);
+
+-- Per-season totals, final_rank is filled in when the season is closed
+CREATE TABLE IF NOT EXISTS wdl_database.quote_season_scores (
+    season_id INT NOT NULL,
+    user_id BIGINT NOT NULL,
+    correct_guesses INT NOT NULL DEFAULT 0,
+    total_attempts INT NOT NULL DEFAULT 0,
+    points INT NOT NULL DEFAULT 0,
+    current_streak INT NOT NULL DEFAULT 0,
+    best_streak INT NOT NULL DEFAULT 0,
+    final_rank INT NULL,
+    PRIMARY KEY (season_id, user_id),
+    CONSTRAINT fk_season_scores_season_id FOREIGN KEY (season_id) REFERENCES quote_seasons(id)
+);
+
+-- Everything played so far belongs to the first season
+INSERT INTO wdl_database.quote_seasons (name) VALUES ('Season 1');
@@ -0,0 +1,29 @@
+-- Scope guessquote data per guild. Existing rows start out with guild id 0 and are
+-- assigned to the guild of DISCORD_CHANNEL_ID when the bot next starts.
+ALTER TABLE wdl_database.discord_messages
+ADD COLUMN GuildId BIGINT NOT NULL DEFAULT 0,
+ADD INDEX idx_guild_user_id (GuildId, UserId);
+
+-- Scores are kept per guild and user
+ALTER TABLE wdl_database.quote_scores
+ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 0 FIRST,
+ADD INDEX idx_scores_user_id (user_id);
+
+ALTER TABLE wdl_database.quote_scores
+DROP PRIMARY KEY,
+ADD PRIMARY KEY (guild_id, user_id);
+
+-- Each guild runs its own seasons, season ids stay unique across guilds
+ALTER TABLE wdl_database.quote_seasons
+ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 0 AFTER id,
+ADD INDEX idx_seasons_guild_id (guild_id);
+
+ALTER TABLE wdl_database.quote_rounds
+ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 0 AFTER id,
+ADD INDEX idx_rounds_guild_id (guild_id);
+
+-- Aliases only apply in the guild that configured them
+ALTER TABLE wdl_database.user_aliases
+ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 0 AFTER id,
+DROP INDEX uq_alias,
+ADD UNIQUE KEY uq_guild_alias (guild_id, alias);
@@ -0,0 +1,5 @@
+-- Skill rating per guild and player, updated after every scored round
+ALTER TABLE wdl_database.quote_scores
+ADD COLUMN rating DOUBLE NOT NULL DEFAULT 1500,
+ADD COLUMN rated_rounds INT NOT NULL DEFAULT 0,
+ADD INDEX idx_scores_guild_rating (guild_id, rating);
@@ -0,0 +1,20 @@
+-- How often each quote was asked in a scored round and how often someone identified it
+ALTER TABLE wdl_database.discord_messages
+ADD COLUMN times_asked INT NOT NULL DEFAULT 0,
+ADD COLUMN times_identified INT NOT NULL DEFAULT 0;
+
+-- Backfill from the rounds played so far
+UPDATE wdl_database.discord_messages m
+JOIN (
+    SELECT r.message_id,
+           COUNT(*) as asked,
+           SUM(EXISTS (
+               SELECT 1 FROM wdl_database.quote_guesses g WHERE g.round_id = r.id AND g.correct = TRUE
+           )) as identified
+    FROM wdl_database.quote_rounds r
+    WHERE r.cancelled = FALSE
+      AND EXISTS (SELECT 1 FROM wdl_database.quote_guesses g WHERE g.round_id = r.id)
+    GROUP BY r.message_id
+) played ON played.message_id = m.Id
+SET m.times_asked = played.asked,
+    m.times_identified = played.identified;
@@ -0,0 +1,36 @@
+-- One quote per guild and day, revealed after midnight UTC
+CREATE TABLE IF NOT EXISTS wdl_database.quote_daily_challenges (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    challenge_date DATE NOT NULL,
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    quoted_user_id BIGINT NOT NULL,
+    candidate_ids VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revealed_at TIMESTAMP NULL,
+    UNIQUE KEY uq_guild_date (guild_id, challenge_date),
+    INDEX idx_daily_unrevealed (revealed_at, challenge_date)
+);
+
+-- Each player's single guess, points are filled in at the reveal
+CREATE TABLE IF NOT EXISTS wdl_database.quote_daily_guesses (
+    challenge_id INT NOT NULL,
+    user_id BIGINT NOT NULL,
+    guessed_user_id BIGINT NOT NULL,
+    correct BOOLEAN NOT NULL,
+    points_awarded INT NULL,
+    guessed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (challenge_id, user_id),
+    CONSTRAINT fk_daily_guesses_challenge_id FOREIGN KEY (challenge_id) REFERENCES quote_daily_challenges(id)
+);
+
+-- Consecutive days with a correct daily guess, separate from the round streaks
+CREATE TABLE IF NOT EXISTS wdl_database.quote_daily_streaks (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    current_streak INT NOT NULL DEFAULT 0,
+    best_streak INT NOT NULL DEFAULT 0,
+    last_correct_date DATE NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
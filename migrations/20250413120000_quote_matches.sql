@@ -0,0 +1,30 @@
+-- A multi-round guessquote match, its rounds link back through quote_rounds.match_id
+CREATE TABLE IF NOT EXISTS wdl_database.quote_matches (
+    id CHAR(36) PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    started_by BIGINT NOT NULL,
+    mode VARCHAR(20) NOT NULL,
+    rounds INT NOT NULL,
+    rounds_played INT NOT NULL DEFAULT 0,
+    winner_id BIGINT NULL,
+    started_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_at TIMESTAMP NULL,
+    cancelled BOOLEAN NOT NULL DEFAULT FALSE,
+    INDEX idx_matches_guild_id (guild_id)
+);
+
+-- Final standings of every player in a match
+CREATE TABLE IF NOT EXISTS wdl_database.quote_match_results (
+    match_id CHAR(36) NOT NULL,
+    user_id BIGINT NOT NULL,
+    points INT NOT NULL,
+    placement INT NOT NULL,
+    PRIMARY KEY (match_id, user_id),
+    INDEX idx_match_results_user_id (user_id),
+    CONSTRAINT fk_match_results_match_id FOREIGN KEY (match_id) REFERENCES quote_matches(id)
+);
+
+ALTER TABLE wdl_database.quote_rounds
+    ADD COLUMN match_id CHAR(36) NULL,
+    ADD CONSTRAINT fk_rounds_match_id FOREIGN KEY (match_id) REFERENCES quote_matches(id);
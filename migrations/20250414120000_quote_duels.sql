@@ -0,0 +1,28 @@
+-- Every accepted duel with its final score
+CREATE TABLE IF NOT EXISTS wdl_database.quote_duels (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    challenger_id BIGINT NOT NULL,
+    opponent_id BIGINT NOT NULL,
+    best_of INT NOT NULL,
+    challenger_wins INT NOT NULL,
+    opponent_wins INT NOT NULL,
+    winner_id BIGINT NULL,
+    outcome VARCHAR(20) NOT NULL,
+    ended_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_duels_guild_id (guild_id),
+    INDEX idx_duels_challenger_id (challenger_id),
+    INDEX idx_duels_opponent_id (opponent_id)
+);
+
+-- Duel rating and record per player, kept apart from the round rating in quote_scores
+CREATE TABLE IF NOT EXISTS wdl_database.quote_duel_ratings (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    rating DOUBLE NOT NULL DEFAULT 1500,
+    wins INT NOT NULL DEFAULT 0,
+    losses INT NOT NULL DEFAULT 0,
+    draws INT NOT NULL DEFAULT 0,
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,16 @@
+-- Every guessquote wager with how it was settled, so balance changes can be traced
+CREATE TABLE IF NOT EXISTS wdl_database.quote_wagers (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    round_id CHAR(36) NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    balance INT NOT NULL,
+    percent INT NOT NULL,
+    stake INT NOT NULL,
+    placed_after_ms BIGINT NOT NULL,
+    outcome VARCHAR(10) NOT NULL,
+    payout INT NOT NULL,
+    settled_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_wagers_guild_user (guild_id, user_id),
+    CONSTRAINT fk_wagers_round_id FOREIGN KEY (round_id) REFERENCES quote_rounds(id)
+);
@@ -0,0 +1,37 @@
+-- Every change to a player's points. Rows are never updated or deleted, a mistake is
+-- corrected with a new row. quote_scores and quote_season_scores are derived from it.
+CREATE TABLE IF NOT EXISTS wdl_database.quote_points_ledger (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    season_id INT NULL,
+    kind VARCHAR(20) NOT NULL,
+    points INT NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    correct INT NOT NULL DEFAULT 0,
+    -- Streaks carried over by opening balances, NULL for every other row
+    streak INT NULL,
+    best_streak INT NULL,
+    round_id CHAR(36) NULL,
+    reverses_id BIGINT NULL,
+    reason VARCHAR(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_520_ci NULL,
+    created_by BIGINT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_ledger_guild_user (guild_id, user_id),
+    INDEX idx_ledger_season_user (season_id, user_id),
+    INDEX idx_ledger_round_id (round_id),
+    UNIQUE KEY uq_ledger_reverses_id (reverses_id),
+    CONSTRAINT fk_ledger_reverses_id FOREIGN KEY (reverses_id) REFERENCES quote_points_ledger(id)
+);
+
+-- Totals from before the ledger carry over as opening balances
+INSERT INTO wdl_database.quote_points_ledger (guild_id, user_id, kind, points, attempts, correct, streak, best_streak, reason)
+SELECT guild_id, user_id, 'opening', points, total_attempts, correct_guesses, current_streak, best_streak, 'Totals before the points ledger'
+FROM wdl_database.quote_scores;
+
+-- Season totals get their own opening balances, which only count for their season
+INSERT INTO wdl_database.quote_points_ledger (guild_id, user_id, season_id, kind, points, attempts, correct, streak, best_streak, reason)
+SELECT seasons.guild_id, scores.user_id, scores.season_id, 'season_opening', scores.points, scores.total_attempts,
+       scores.correct_guesses, scores.current_streak, scores.best_streak, 'Season totals before the points ledger'
+FROM wdl_database.quote_season_scores scores
+JOIN wdl_database.quote_seasons seasons ON seasons.id = scores.season_id;
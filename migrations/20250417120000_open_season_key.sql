@@ -0,0 +1,16 @@
+-- Rounds starting together in a new guild could each open its first season,
+-- keep the newest open season of every guild, which is the one scores went to
+UPDATE wdl_database.quote_seasons seasons
+JOIN (
+    SELECT guild_id, MAX(id) AS open_id
+    FROM wdl_database.quote_seasons
+    WHERE ended_at IS NULL
+    GROUP BY guild_id
+) latest ON latest.guild_id = seasons.guild_id
+SET seasons.ended_at = CURRENT_TIMESTAMP
+WHERE seasons.ended_at IS NULL AND seasons.id <> latest.open_id;
+
+-- A guild has at most one open season, closed seasons leave the key NULL
+ALTER TABLE wdl_database.quote_seasons
+ADD COLUMN open_guild_id BIGINT GENERATED ALWAYS AS (IF(ended_at IS NULL, guild_id, NULL)) STORED,
+ADD UNIQUE KEY uq_seasons_open_guild_id (open_guild_id);
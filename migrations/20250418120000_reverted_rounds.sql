@@ -0,0 +1,3 @@
+-- Rounds an admin reverted stay in the history but no longer count in stats
+ALTER TABLE wdl_database.quote_rounds
+ADD COLUMN reverted_at TIMESTAMP NULL DEFAULT NULL;
@@ -18,7 +18,7 @@ pub async fn handle_commands(ctx: Context, command: &CommandInteraction) -> Resu
     // Defer the response to buy time to fetch data
     command.defer(&ctx.http).await?;
     
-    match command.data.options.get(0) {
+    match command.data.options.first() {
         Some(option) => match option.name.as_str() {
             "next" => show_next_race(ctx, command).await?,
             "season" => show_season_races(ctx, command).await?,
@@ -25,7 +25,7 @@ pub fn create_race_embed(race: &Race) -> CreateEmbed {
         ))
         .field(
             "Circuit",
-            format!("{}", race.circuit.circuit_name),
+            race.circuit.circuit_name.clone(),
             true
         )
         .field(
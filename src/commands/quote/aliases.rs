@@ -0,0 +1,191 @@
+use log::{info, warn};
+use serenity::all::{
+    CommandInteraction, CommandOptionType, CreateCommand, CreateCommandOption,
+    CreateInteractionResponse, CreateInteractionResponseMessage, GuildId, Permissions, ResolvedOption,
+    ResolvedValue,
+};
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+
+pub fn register() -> CreateCommand {
+    let add_option = CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add an alias that counts as a guess for a user")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "The user the alias belongs to").required(true))
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "alias", "The nickname players may type").required(true).max_length(100));
+    let remove_option = CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove an alias")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "alias", "The alias to remove").required(true));
+    let list_option = CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List the known aliases")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "Only show aliases for this user"));
+
+    CreateCommand::new("quotealias")
+        .description("Manage the nicknames accepted as guessquote answers")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .add_option(add_option)
+        .add_option(remove_option)
+        .add_option(list_option)
+}
+
+// Command handler for the quotealias command and its subcommands
+pub async fn handle_commands(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let options = command.data.options();
+    let reply = match (command.guild_id, options.first()) {
+        (None, _) => "Aliases can only be managed in servers.".to_string(),
+        (Some(guild_id), Some(ResolvedOption { name: "add", value: ResolvedValue::SubCommand(options), .. })) => {
+            add_alias(command, guild_id, options, db_pool).await?
+        }
+        (Some(guild_id), Some(ResolvedOption { name: "remove", value: ResolvedValue::SubCommand(options), .. })) => {
+            remove_alias(guild_id, options, db_pool).await?
+        }
+        (Some(guild_id), Some(ResolvedOption { name: "list", value: ResolvedValue::SubCommand(options), .. })) => {
+            list_aliases(guild_id, options, db_pool).await?
+        }
+        _ => "Unknown subcommand.".to_string(),
+    };
+
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(reply).ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+async fn add_alias(
+    command: &CommandInteraction,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+    db_pool: &MySqlPool,
+) -> Result<String, sqlx::Error> {
+    let user = options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) if option.name == "user" => Some(user),
+        _ => None,
+    });
+    let alias = options.iter().find_map(|option| match option.value {
+        ResolvedValue::String(alias) if option.name == "alias" => Some(alias.trim()),
+        _ => None,
+    });
+
+    let (Some(user), Some(alias)) = (user, alias) else {
+        return Ok("Both a user and an alias are required.".to_string());
+    };
+    if alias.is_empty() {
+        return Ok("The alias can't be empty.".to_string());
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO wdl_database.user_aliases (guild_id, user_id, alias, created_by)
+         VALUES (?, ?, ?, ?)
+         ON DUPLICATE KEY UPDATE user_id = VALUES(user_id), created_by = VALUES(created_by)",
+    )
+        .bind(i64::from(guild_id))
+        .bind(i64::from(user.id))
+        .bind(alias)
+        .bind(i64::from(command.user.id))
+        .execute(db_pool)
+        .await?;
+
+    info!("Alias {:?} now points at user {} ({} rows affected)", alias, user.id, result.rows_affected());
+    Ok(format!("Guesses of **{}** now count for <@{}>.", alias, user.id))
+}
+
+async fn remove_alias(guild_id: GuildId, options: &[ResolvedOption<'_>], db_pool: &MySqlPool) -> Result<String, sqlx::Error> {
+    let Some(alias) = options.iter().find_map(|option| match option.value {
+        ResolvedValue::String(alias) if option.name == "alias" => Some(alias.trim()),
+        _ => None,
+    }) else {
+        return Ok("An alias is required.".to_string());
+    };
+
+    let result = sqlx::query("DELETE FROM wdl_database.user_aliases WHERE guild_id = ? AND alias = ?")
+        .bind(i64::from(guild_id))
+        .bind(alias)
+        .execute(db_pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        Ok(format!("There is no alias called **{}**.", alias))
+    } else {
+        info!("Removed alias {:?}", alias);
+        Ok(format!("Removed the alias **{}**.", alias))
+    }
+}
+
+async fn list_aliases(guild_id: GuildId, options: &[ResolvedOption<'_>], db_pool: &MySqlPool) -> Result<String, sqlx::Error> {
+    let user_id = options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) if option.name == "user" => Some(i64::from(user.id)),
+        _ => None,
+    });
+
+    let rows = match user_id {
+        Some(user_id) => {
+            sqlx::query_as::<_, (i64, String)>(
+                "SELECT user_id, alias FROM wdl_database.user_aliases WHERE guild_id = ? AND user_id = ? ORDER BY alias",
+            )
+                .bind(i64::from(guild_id))
+                .bind(user_id)
+                .fetch_all(db_pool)
+                .await?
+        }
+        None => {
+            sqlx::query_as::<_, (i64, String)>(
+                "SELECT user_id, alias FROM wdl_database.user_aliases WHERE guild_id = ? ORDER BY user_id, alias",
+            )
+                .bind(i64::from(guild_id))
+                .fetch_all(db_pool)
+                .await?
+        }
+    };
+
+    if rows.is_empty() {
+        return Ok("No aliases configured yet.".to_string());
+    }
+
+    let mut grouped: Vec<(i64, Vec<String>)> = Vec::new();
+    for (user_id, alias) in rows {
+        match grouped.last_mut() {
+            Some((last_user, aliases)) if *last_user == user_id => aliases.push(alias),
+            _ => grouped.push((user_id, vec![alias])),
+        }
+    }
+
+    let mut listing = String::from("**Guessquote aliases**\n");
+    for (user_id, aliases) in grouped {
+        listing.push_str(&format!("<@{}>: {}\n", user_id, aliases.join(", ")));
+    }
+    Ok(listing)
+}
+
+/// Loads all aliases the guild configured for the given users, keyed by user id.
+pub async fn fetch_aliases(db_pool: &MySqlPool, guild_id: GuildId, user_ids: &[i64]) -> HashMap<i64, Vec<String>> {
+    let mut aliases: HashMap<i64, Vec<String>> = HashMap::new();
+    if user_ids.is_empty() {
+        return aliases;
+    }
+
+    let mut query_builder = sqlx::QueryBuilder::new("SELECT user_id, alias FROM wdl_database.user_aliases WHERE guild_id = ");
+    query_builder.push_bind(i64::from(guild_id));
+    query_builder.push(" AND user_id IN (");
+    let mut separated = query_builder.separated(", ");
+    for &id in user_ids {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(")");
+
+    match query_builder.build_query_as::<(i64, String)>().fetch_all(db_pool).await {
+        Ok(rows) => {
+            for (user_id, alias) in rows {
+                aliases.entry(user_id).or_default().push(alias);
+            }
+        }
+        Err(e) => warn!("Failed to fetch user aliases: {}", e),
+    }
+
+    aliases
+}
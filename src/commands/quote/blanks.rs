@@ -0,0 +1,177 @@
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use std::time::Duration;
+
+use super::matching::{allowed_distance, edit_distance, normalize};
+use super::scoring::{self, GuessPoints};
+use super::RoundRules;
+
+/// Shortest quote worth finishing, shorter ones leave too little context.
+pub const MIN_QUOTE_LENGTH: u32 = 60;
+/// Words shorter than this are never blanked, they are too easy to guess.
+const MIN_WORD_LENGTH: usize = 4;
+
+/// Common words that carry no meaning of their own and make poor blanks.
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "again", "also", "been", "before", "being", "both", "came", "come",
+    "could", "does", "doing", "done", "down", "each", "even", "ever", "from", "going",
+    "gonna", "have", "having", "here", "into", "just", "know", "like", "made", "make",
+    "many", "more", "most", "much", "must", "only", "other", "over", "really", "said",
+    "same", "should", "some", "such", "than", "that", "their", "them", "then", "there",
+    "these", "they", "thing", "this", "those", "very", "want", "were", "what", "when",
+    "where", "which", "while", "with", "would", "your", "yours", "youre", "dont", "cant",
+    "didnt", "doesnt", "isnt", "wasnt", "thats", "theres", "well", "still", "because",
+];
+
+/// A quote with some of its words replaced by numbered blanks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlankedQuote {
+    /// The quote as shown to players.
+    pub text: String,
+    /// The full quote with the missing words in bold, for the reveal.
+    pub revealed: String,
+    /// The missing words, in the order of their blanks.
+    pub answers: Vec<String>,
+}
+
+/// The bare word of a token worth blanking. Mentions, channels, custom emoji,
+/// links, numbers, emoji and stop words are skipped.
+fn significant_word(token: &str) -> Option<&str> {
+    if token.starts_with('<') || token.contains("://") {
+        return None;
+    }
+    let word = token.trim_matches(|c: char| !c.is_alphanumeric());
+    let significant = word.chars().count() >= MIN_WORD_LENGTH
+        && word.chars().all(char::is_alphabetic)
+        && !STOP_WORDS.contains(&word.to_lowercase().as_str());
+    significant.then_some(word)
+}
+
+/// Longer quotes get more blanks.
+fn blank_count(significant_words: usize) -> usize {
+    match significant_words {
+        0..=5 => 1,
+        6..=11 => 2,
+        _ => 3,
+    }
+}
+
+/// Blanks out random significant words, or `None` when the quote has none.
+pub fn blank_out(content: &str, rng: &mut impl Rng) -> Option<BlankedQuote> {
+    let tokens: Vec<&str> = content.split(' ').collect();
+    let significant: Vec<usize> = tokens.iter()
+        .enumerate()
+        .filter(|(_, token)| significant_word(token).is_some())
+        .map(|(index, _)| index)
+        .collect();
+    if significant.is_empty() {
+        return None;
+    }
+
+    let mut blanked: Vec<usize> = significant.choose_multiple(rng, blank_count(significant.len())).copied().collect();
+    blanked.sort_unstable();
+
+    let mut shown = Vec::with_capacity(tokens.len());
+    let mut revealed = Vec::with_capacity(tokens.len());
+    let mut answers = Vec::with_capacity(blanked.len());
+    for (index, token) in tokens.iter().enumerate() {
+        match (blanked.binary_search(&index), significant_word(token)) {
+            (Ok(_), Some(word)) => {
+                answers.push(word.to_string());
+                shown.push(token.replacen(word, &format!("`[{}]____`", answers.len()), 1));
+                revealed.push(token.replacen(word, &format!("**{}**", word), 1));
+            }
+            _ => {
+                shown.push(token.to_string());
+                revealed.push(token.to_string());
+            }
+        }
+    }
+
+    Some(BlankedQuote {
+        text: shown.join(" "),
+        revealed: revealed.join(" "),
+        answers,
+    })
+}
+
+/// Which blanks a reply fills, in blank order. Words may come in any order
+/// and small typos are tolerated like for names.
+pub fn filled_blanks(content: &str, answers: &[String]) -> Vec<bool> {
+    let words = normalize(content);
+    answers.iter()
+        .map(|answer| {
+            let answer = answer.to_lowercase();
+            let max_distance = allowed_distance(answer.chars().count());
+            words.iter().any(|word| *word == answer || edit_distance(word, &answer) <= max_distance)
+        })
+        .collect()
+}
+
+/// Scores a reply that filled `filled` of `total` blanks. Partial answers earn
+/// their share of the time points, only complete ones keep the streak going.
+pub fn score_guess(filled: usize, total: usize, elapsed: Duration, current_streak: i32, rules: &RoundRules) -> GuessPoints {
+    if filled == 0 || total == 0 {
+        return scoring::score_guess(false, elapsed, current_streak, 1.0, rules);
+    }
+
+    let share = filled as f64 / total as f64;
+    GuessPoints {
+        base: (scoring::time_points(elapsed, rules) as f64 * share).round() as i32,
+        streak_bonus: if filled == total { scoring::streak_bonus(current_streak, rules) } else { 0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn only_significant_words_are_blanked() {
+        assert_eq!(significant_word("pineapple,"), Some("pineapple"));
+        assert_eq!(significant_word("that"), None);
+        assert_eq!(significant_word("cat"), None);
+        assert_eq!(significant_word("<@121751619149758464>"), None);
+        assert_eq!(significant_word("<:pepe:123>"), None);
+        assert_eq!(significant_word("https://example.com"), None);
+        assert_eq!(significant_word("🍍🍍🍍🍍"), None);
+        assert_eq!(significant_word("2023"), None);
+    }
+
+    #[test]
+    fn blanks_keep_the_rest_of_the_quote() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let blanked = blank_out("that is <@1> a pineapple!", &mut rng).unwrap();
+        assert_eq!(blanked.text, "that is <@1> a `[1]____`!");
+        assert_eq!(blanked.revealed, "that is <@1> a **pineapple**!");
+        assert_eq!(blanked.answers, vec!["pineapple".to_string()]);
+        assert_eq!(blank_out("that is it", &mut rng), None);
+    }
+
+    #[test]
+    fn longer_quotes_get_more_blanks() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let quote = "pineapple pizza remains controversial among serious italian chefs everywhere tonight";
+        let blanked = blank_out(quote, &mut rng).unwrap();
+        assert_eq!(blanked.answers.len(), 2);
+        assert_eq!(blanked.text.matches("____").count(), 2);
+    }
+
+    #[test]
+    fn replies_tolerate_typos() {
+        let answers = vec!["pineapple".to_string(), "pizza".to_string()];
+        assert_eq!(filled_blanks("Pizza and pinapple", &answers), vec![true, true]);
+        assert_eq!(filled_blanks("pineapple pasta", &answers), vec![true, false]);
+        assert_eq!(filled_blanks("no clue", &answers), vec![false, false]);
+    }
+
+    #[test]
+    fn partial_answers_earn_a_share() {
+        let rules = RoundRules::default();
+        assert_eq!(score_guess(2, 2, Duration::ZERO, 2, &rules), GuessPoints { base: 100, streak_bonus: 10 });
+        assert_eq!(score_guess(1, 2, Duration::ZERO, 2, &rules), GuessPoints { base: 50, streak_bonus: 0 });
+        assert_eq!(score_guess(0, 2, Duration::ZERO, 2, &rules).total(), -5);
+    }
+}
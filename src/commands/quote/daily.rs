@@ -0,0 +1,440 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use log::{info, warn};
+use serenity::all::{
+    ButtonStyle, ChannelId, CommandInteraction, CommandOptionType, ComponentInteraction,
+    CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, GuildId,
+};
+use sqlx::{MySql, MySqlPool, Transaction};
+use std::time::Duration;
+
+use super::difficulty;
+use super::selection::{QuoteFilter, QuotePool};
+use super::{allowed_users, fetch_candidates, fetch_latest_names, ledger, scoring, seasons};
+use crate::GUESSQUOTE_RULES;
+
+pub const DAILY_BUTTON_PREFIX: &str = "daily:";
+
+/// A guild's quote for one day, answered by buttons for the candidates.
+struct Challenge {
+    id: i32,
+    message_id: i64,
+    candidate_ids: Vec<i64>,
+}
+
+pub fn register() -> CreateCommand {
+    let play_option = CreateCommandOption::new(CommandOptionType::SubCommand, "play", "Show today's quote, everyone gets one guess");
+    let leaderboard_option = CreateCommandOption::new(CommandOptionType::SubCommand, "leaderboard", "Show the daily challenge streaks");
+
+    CreateCommand::new("daily")
+        .description("The daily quote challenge, answers are revealed at midnight UTC")
+        .dm_permission(false)
+        .add_option(play_option)
+        .add_option(leaderboard_option)
+}
+
+// Command handler for the daily command and its subcommands
+pub async fn handle_commands(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+    quotes: &QuotePool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = command.guild_id else {
+        return Err("daily command used outside of a guild".into());
+    };
+
+    let response = match command.data.options.first().map(|option| option.name.as_str()) {
+        Some("play") => show_challenge(db_pool, quotes, guild_id, command.channel_id).await?,
+        Some("leaderboard") => {
+            let yesterday = today() - Days::new(1);
+            CreateInteractionResponseMessage::new()
+                .content(format!("🔥 **Daily challenge streaks**\n\n{}", streak_leaderboard(db_pool, guild_id, yesterday).await?))
+        }
+        _ => CreateInteractionResponseMessage::new().content("Unknown subcommand.").ephemeral(true),
+    };
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+/// Challenges follow the UTC calendar.
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+/// Loads the guild's challenge for today, creating it on first use. The first
+/// channel it is played in is where the answer gets revealed.
+async fn todays_challenge(
+    db_pool: &MySqlPool,
+    quotes: &QuotePool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<Option<Challenge>, Box<dyn std::error::Error + Send + Sync>> {
+    let today = today();
+    if let Some(challenge) = fetch_challenge(db_pool, guild_id, today).await? {
+        return Ok(Some(challenge));
+    }
+
+    let rules = GUESSQUOTE_RULES.get().cloned().unwrap_or_default();
+    let filter = QuoteFilter { min_length: rules.min_length, difficulty: None, excluded_authors: Vec::new() };
+    let Some(message_id) = quotes.pick(db_pool, guild_id, channel_id, &filter).await? else {
+        return Ok(None);
+    };
+    let (quoted_user_id,) = sqlx::query_as::<_, (i64,)>("SELECT UserId FROM wdl_database.discord_messages WHERE Id = ?")
+        .bind(message_id)
+        .fetch_one(db_pool)
+        .await?;
+
+    let candidate_ids: Vec<String> = fetch_candidates(db_pool, quoted_user_id, allowed_users(guild_id))
+        .await
+        .into_iter()
+        .map(|(user_id, _)| user_id.to_string())
+        .collect();
+
+    // Another player may have opened the challenge at the same time, theirs wins
+    sqlx::query(
+        "INSERT IGNORE INTO wdl_database.quote_daily_challenges
+         (guild_id, challenge_date, channel_id, message_id, quoted_user_id, candidate_ids)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+        .bind(i64::from(guild_id))
+        .bind(today)
+        .bind(i64::from(channel_id))
+        .bind(message_id)
+        .bind(quoted_user_id)
+        .bind(candidate_ids.join(","))
+        .execute(db_pool)
+        .await?;
+    info!("Opened the daily challenge of {} for guild {} with quote {}", today, guild_id, message_id);
+
+    Ok(fetch_challenge(db_pool, guild_id, today).await?)
+}
+
+async fn fetch_challenge(db_pool: &MySqlPool, guild_id: GuildId, date: NaiveDate) -> Result<Option<Challenge>, sqlx::Error> {
+    let row = sqlx::query_as::<_, (i32, i64, String)>(
+        "SELECT id, message_id, candidate_ids
+         FROM wdl_database.quote_daily_challenges
+         WHERE guild_id = ? AND challenge_date = ?",
+    )
+        .bind(i64::from(guild_id))
+        .bind(date)
+        .fetch_optional(db_pool)
+        .await?;
+
+    Ok(row.map(|(id, message_id, candidate_ids)| Challenge {
+        id,
+        message_id,
+        candidate_ids: candidate_ids.split(',').filter_map(|id| id.parse().ok()).collect(),
+    }))
+}
+
+async fn show_challenge(
+    db_pool: &MySqlPool,
+    quotes: &QuotePool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<CreateInteractionResponseMessage, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(challenge) = todays_challenge(db_pool, quotes, guild_id, channel_id).await? else {
+        return Ok(CreateInteractionResponseMessage::new()
+            .content("There are no quotes to pick today's challenge from.")
+            .ephemeral(true));
+    };
+
+    let (content,) = sqlx::query_as::<_, (String,)>("SELECT Content FROM wdl_database.discord_messages WHERE Id = ?")
+        .bind(challenge.message_id)
+        .fetch_one(db_pool)
+        .await?;
+    let names = fetch_latest_names(db_pool, &challenge.candidate_ids).await;
+
+    let buttons = challenge.candidate_ids.iter()
+        .map(|user_id| {
+            let name = names.get(user_id).cloned().unwrap_or_else(|| user_id.to_string());
+            CreateButton::new(format!("{}{}:{}", DAILY_BUTTON_PREFIX, challenge.id, user_id))
+                .label(name.chars().take(80).collect::<String>())
+                .style(ButtonStyle::Secondary)
+        })
+        .collect();
+
+    Ok(CreateInteractionResponseMessage::new()
+        .content(format!(
+            "📅 **Daily quote challenge for {}**\n\n> _{}_\n\nWho said it? You get one guess, the answer is revealed at midnight UTC.",
+            today(), content
+        ))
+        .components(vec![CreateActionRow::Buttons(buttons)]))
+}
+
+/// Handles a press on one of the daily challenge buttons. Only the first guess
+/// of each player counts and whether it was right stays hidden until the reveal.
+pub async fn handle_component(
+    ctx: serenity::client::Context,
+    interaction: &ComponentInteraction,
+    db_pool: &MySqlPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let picked = interaction.data.custom_id
+        .strip_prefix(DAILY_BUTTON_PREFIX)
+        .and_then(|ids| ids.split_once(':'))
+        .and_then(|(challenge_id, user_id)| Some((challenge_id.parse::<i32>().ok()?, user_id.parse::<i64>().ok()?)));
+    let Some((challenge_id, picked)) = picked else {
+        return Err(format!("unknown daily component: {}", interaction.data.custom_id).into());
+    };
+
+    let challenge = sqlx::query_as::<_, (i64, NaiveDate)>(
+        "SELECT quoted_user_id, challenge_date FROM wdl_database.quote_daily_challenges
+         WHERE id = ? AND revealed_at IS NULL",
+    )
+        .bind(challenge_id)
+        .fetch_optional(db_pool)
+        .await?;
+
+    let reply = match challenge {
+        Some((quoted_user_id, date)) if date == today() => {
+            let result = sqlx::query(
+                "INSERT IGNORE INTO wdl_database.quote_daily_guesses (challenge_id, user_id, guessed_user_id, correct)
+                 VALUES (?, ?, ?, ?)",
+            )
+                .bind(challenge_id)
+                .bind(i64::from(interaction.user.id))
+                .bind(picked)
+                .bind(picked == quoted_user_id)
+                .execute(db_pool)
+                .await?;
+
+            if result.rows_affected() == 0 {
+                "You already locked in your guess for today.".to_string()
+            } else {
+                info!("Daily guess from {} for challenge {}: {}", interaction.user.id, challenge_id, picked);
+                format!("Locked in <@{}>. The answer is revealed at midnight UTC!", picked)
+            }
+        }
+        _ => "This challenge is closed, try today's with `/daily play`.".to_string(),
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(reply).ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Time left until the next UTC midnight.
+fn until_midnight(now: DateTime<Utc>) -> Duration {
+    let next_midnight = (now.date_naive() + Days::new(1)).and_hms_opt(0, 0, 0).expect("midnight is a valid time").and_utc();
+    (next_midnight - now).to_std().unwrap_or_default()
+}
+
+/// Reveals every finished challenge right away, then again after each midnight.
+pub fn spawn_reveals(ctx: serenity::client::Context, db_pool: MySqlPool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = reveal_due(&ctx, &db_pool).await {
+                warn!("Failed to reveal daily challenges: {}", e);
+            }
+            // A little slack so the new day has started everywhere
+            tokio::time::sleep(until_midnight(Utc::now()) + Duration::from_secs(5)).await;
+        }
+    });
+}
+
+async fn reveal_due(ctx: &serenity::client::Context, db_pool: &MySqlPool) -> Result<(), sqlx::Error> {
+    let due = sqlx::query_as::<_, (i32, i64, NaiveDate, i64, i64, i64)>(
+        "SELECT id, guild_id, challenge_date, channel_id, message_id, quoted_user_id
+         FROM wdl_database.quote_daily_challenges
+         WHERE challenge_date < ? AND revealed_at IS NULL
+         ORDER BY challenge_date",
+    )
+        .bind(today())
+        .fetch_all(db_pool)
+        .await?;
+
+    for (id, guild_id, date, channel_id, message_id, quoted_user_id) in due {
+        let guild_id = GuildId::new(guild_id as u64);
+        match reveal_challenge(db_pool, id, guild_id, date, message_id, quoted_user_id).await {
+            Ok(Some(announcement)) => {
+                if let Err(e) = ChannelId::new(channel_id as u64).say(&ctx.http, announcement).await {
+                    warn!("Failed to post the daily reveal for guild {}: {}", guild_id, e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to reveal daily challenge {}: {}", id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Scores a finished challenge and builds its announcement. Returns `None` when
+/// another task already revealed it. The challenge is claimed and scored in one
+/// transaction, so a failed reveal leaves it unrevealed for the next attempt.
+async fn reveal_challenge(
+    db_pool: &MySqlPool,
+    challenge_id: i32,
+    guild_id: GuildId,
+    date: NaiveDate,
+    message_id: i64,
+    quoted_user_id: i64,
+) -> Result<Option<String>, sqlx::Error> {
+    let season = seasons::current_season(db_pool, guild_id).await;
+    let mut transaction = db_pool.begin().await?;
+
+    let claimed = sqlx::query(
+        "UPDATE wdl_database.quote_daily_challenges SET revealed_at = CURRENT_TIMESTAMP WHERE id = ? AND revealed_at IS NULL",
+    )
+        .bind(challenge_id)
+        .execute(&mut *transaction)
+        .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let (content, name, times_asked, times_identified) = sqlx::query_as::<_, (String, String, i32, i32)>(
+        "SELECT Content, Name, times_asked, times_identified FROM wdl_database.discord_messages WHERE Id = ?",
+    )
+        .bind(message_id)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+    let guesses = sqlx::query_as::<_, (i64, bool)>(
+        "SELECT user_id, correct FROM wdl_database.quote_daily_guesses WHERE challenge_id = ? ORDER BY guessed_at",
+    )
+        .bind(challenge_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    let rules = GUESSQUOTE_RULES.get().cloned().unwrap_or_default();
+    let multiplier = difficulty::points_multiplier(difficulty::difficulty(times_asked, times_identified));
+
+    let mut correct_lines = Vec::new();
+    let mut incorrect_lines = Vec::new();
+    for &(user_id, is_correct) in guesses.iter() {
+        // Daily streaks only carry on when yesterday's challenge was solved too
+        let streak = sqlx::query_as::<_, (i32, Option<NaiveDate>)>(
+            "SELECT current_streak, last_correct_date FROM wdl_database.quote_daily_streaks WHERE guild_id = ? AND user_id = ?",
+        )
+            .bind(i64::from(guild_id))
+            .bind(user_id)
+            .fetch_optional(&mut *transaction)
+            .await?
+            .filter(|(_, last_correct_date)| *last_correct_date == Some(date - Days::new(1)))
+            .map(|(current_streak, _)| current_streak)
+            .unwrap_or(0);
+
+        // A daily guess has no clock, so it scores like an instant answer
+        let points = scoring::score_guess(is_correct, Duration::ZERO, streak, multiplier, &rules).total();
+        let season_id = season.as_ref().map(|(season_id, _)| *season_id);
+        ledger::append(&mut transaction, &ledger::Entry::daily(guild_id, user_id, season_id, points)).await?;
+        sqlx::query("UPDATE wdl_database.quote_daily_guesses SET points_awarded = ? WHERE challenge_id = ? AND user_id = ?")
+            .bind(points)
+            .bind(challenge_id)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        update_streak(&mut transaction, guild_id, user_id, is_correct, streak, date).await?;
+
+        if is_correct {
+            correct_lines.push(format!("✅ <@{}> +{} points (🔥 {} days)", user_id, points, streak + 1));
+        } else {
+            incorrect_lines.push(format!("❌ <@{}> {} points", user_id, points));
+        }
+    }
+    transaction.commit().await?;
+
+    if !guesses.is_empty() {
+        difficulty::record_round(db_pool, message_id, !correct_lines.is_empty()).await;
+    }
+    info!("Revealed daily challenge {} of guild {}: {} guesses", challenge_id, guild_id, guesses.len());
+
+    let mut announcement = format!(
+        "📅 **Daily challenge of {}**\n\n> _{}_\n\nThe quote was from <@{}> ({}).\n\n",
+        date, content, quoted_user_id, name
+    );
+    if guesses.is_empty() {
+        announcement.push_str("Nobody took a guess.\n");
+    }
+    for line in correct_lines.iter().chain(incorrect_lines.iter()) {
+        announcement.push_str(line);
+        announcement.push('\n');
+    }
+    announcement.push_str(&format!(
+        "\n🔥 **Daily streaks**\n{}\nToday's challenge is up, play it with `/daily play`!",
+        streak_leaderboard(db_pool, guild_id, date).await?
+    ));
+
+    Ok(Some(announcement))
+}
+
+async fn update_streak(
+    transaction: &mut Transaction<'_, MySql>,
+    guild_id: GuildId,
+    user_id: i64,
+    is_correct: bool,
+    streak: i32,
+    date: NaiveDate,
+) -> Result<(), sqlx::Error> {
+    let current_streak = if is_correct { streak + 1 } else { 0 };
+    sqlx::query(
+        "INSERT INTO wdl_database.quote_daily_streaks (guild_id, user_id, current_streak, best_streak, last_correct_date)
+         VALUES (?, ?, ?, ?, ?)
+         ON DUPLICATE KEY UPDATE
+         current_streak = VALUES(current_streak),
+         best_streak = GREATEST(best_streak, VALUES(best_streak)),
+         last_correct_date = COALESCE(VALUES(last_correct_date), last_correct_date)",
+    )
+        .bind(i64::from(guild_id))
+        .bind(user_id)
+        .bind(current_streak)
+        .bind(current_streak)
+        .bind(if is_correct { Some(date) } else { None })
+        .execute(&mut **transaction)
+        .await?;
+    Ok(())
+}
+
+/// Lists the longest daily streaks. A streak is only still running when the
+/// player solved the challenge of `latest_day`, the last one revealed.
+async fn streak_leaderboard(db_pool: &MySqlPool, guild_id: GuildId, latest_day: NaiveDate) -> Result<String, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, i32, i32)>(
+        "SELECT user_id,
+                IF(last_correct_date >= ?, current_streak, 0) as streak,
+                best_streak
+         FROM wdl_database.quote_daily_streaks
+         WHERE guild_id = ? AND best_streak > 0
+         ORDER BY streak DESC, best_streak DESC
+         LIMIT 10",
+    )
+        .bind(latest_day)
+        .bind(i64::from(guild_id))
+        .fetch_all(db_pool)
+        .await?;
+
+    if rows.is_empty() {
+        return Ok("No streaks yet! Start one with `/daily play`\n".to_string());
+    }
+
+    let user_ids: Vec<i64> = rows.iter().map(|(user_id, _, _)| *user_id).collect();
+    let names = fetch_latest_names(db_pool, &user_ids).await;
+    let mut leaderboard = String::new();
+    for (index, (user_id, streak, best_streak)) in rows.iter().enumerate() {
+        let name = names.get(user_id).cloned().unwrap_or_else(|| user_id.to_string());
+        leaderboard.push_str(&format!("{}. {} - {} days (Best: {})\n", index + 1, name, streak, best_streak));
+    }
+    Ok(leaderboard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleeps_until_the_next_utc_midnight() {
+        let now = "2025-04-12T23:59:30Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(until_midnight(now), Duration::from_secs(30));
+        let midnight = "2025-04-12T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(until_midnight(midnight), Duration::from_secs(24 * 60 * 60));
+    }
+}
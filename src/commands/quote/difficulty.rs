@@ -0,0 +1,128 @@
+use log::warn;
+use serenity::all::{CommandOptionType, CreateCommandOption, ResolvedOption, ResolvedValue};
+use sqlx::{Executor, MySql, MySqlPool};
+
+/// Quotes below this difficulty count as easy.
+const EASY_BELOW: f64 = 0.4;
+/// Quotes above this difficulty count as hard.
+const HARD_ABOVE: f64 = 0.6;
+
+/// SQL for the difficulty of a `discord_messages` row, matching [`difficulty`].
+pub const DIFFICULTY_SQL: &str = "CAST(1 - (times_identified + 1) / (times_asked + 2) AS DOUBLE)";
+
+/// The difficulty band players can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Reads the `difficulty` option, rounds without one pick from any band.
+    pub fn from_options(options: &[ResolvedOption]) -> Option<Self> {
+        options.iter().find_map(|option| match option.value {
+            ResolvedValue::String("easy") if option.name == "difficulty" => Some(Difficulty::Easy),
+            ResolvedValue::String("normal") if option.name == "difficulty" => Some(Difficulty::Normal),
+            ResolvedValue::String("hard") if option.name == "difficulty" => Some(Difficulty::Hard),
+            _ => None,
+        })
+    }
+
+    /// The band a difficulty value falls in.
+    pub fn of(difficulty: f64) -> Self {
+        if difficulty < EASY_BELOW {
+            Difficulty::Easy
+        } else if difficulty > HARD_ABOVE {
+            Difficulty::Hard
+        } else {
+            Difficulty::Normal
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "difficulty", "How well known the quote should be")
+        .add_string_choice("Easy", "easy")
+        .add_string_choice("Normal", "normal")
+        .add_string_choice("Hard", "hard")
+}
+
+/// How hard a quote is from 0.0 (always identified) to 1.0 (never identified).
+/// Smoothed so quotes that were rarely asked stay close to the middle.
+pub fn difficulty(times_asked: i32, times_identified: i32) -> f64 {
+    1.0 - (times_identified.max(0) as f64 + 1.0) / (times_asked.max(0) as f64 + 2.0)
+}
+
+/// Multiplier for the points of a correct guess, from 0.5x for the easiest
+/// quotes up to 1.5x for the hardest.
+pub fn points_multiplier(difficulty: f64) -> f64 {
+    0.5 + difficulty.clamp(0.0, 1.0)
+}
+
+/// Counts a scored round towards the quote's difficulty.
+pub async fn record_round(db_pool: &MySqlPool, message_id: i64, identified: bool) {
+    if let Err(e) = sqlx::query(
+        "UPDATE wdl_database.discord_messages
+         SET times_asked = times_asked + 1, times_identified = times_identified + ?
+         WHERE Id = ?",
+    )
+        .bind(if identified { 1 } else { 0 })
+        .bind(message_id)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to update difficulty of quote {}: {}", message_id, e);
+    }
+}
+
+/// Takes a reverted round back out of the quote's difficulty.
+pub async fn unrecord_round<'e>(
+    executor: impl Executor<'e, Database = MySql>,
+    message_id: i64,
+    identified: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE wdl_database.discord_messages
+         SET times_asked = GREATEST(times_asked - 1, 0), times_identified = GREATEST(times_identified - ?, 0)
+         WHERE Id = ?",
+    )
+        .bind(if identified { 1 } else { 0 })
+        .bind(message_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_quotes_are_normal() {
+        assert_eq!(difficulty(0, 0), 0.5);
+        assert_eq!(Difficulty::of(difficulty(0, 0)), Difficulty::Normal);
+    }
+
+    #[test]
+    fn difficulty_follows_how_often_quotes_are_identified() {
+        assert_eq!(Difficulty::of(difficulty(3, 3)), Difficulty::Easy);
+        assert_eq!(Difficulty::of(difficulty(3, 0)), Difficulty::Hard);
+        assert_eq!(Difficulty::of(difficulty(10, 5)), Difficulty::Normal);
+    }
+
+    #[test]
+    fn harder_quotes_are_worth_more() {
+        assert_eq!(points_multiplier(0.5), 1.0);
+        assert!(points_multiplier(difficulty(3, 0)) > 1.0);
+        assert!(points_multiplier(difficulty(3, 3)) < 1.0);
+    }
+}
@@ -0,0 +1,540 @@
+use log::{info, warn};
+use serenity::all::{
+    ButtonStyle, CommandInteraction, CommandOptionType, CreateActionRow, CreateButton, CreateCommand,
+    CreateCommandOption, CreateInteractionResponse, CreateInteractionResponseMessage, ComponentInteractionCollector,
+    ChannelId, EditInteractionResponse, GuildId, ResolvedValue, User, UserId,
+};
+use serenity::futures::StreamExt;
+use sqlx::MySqlPool;
+use std::time::{Duration, Instant};
+
+use super::fairplay;
+use super::games::{GameRegistry, RoundState};
+use super::matches::INTERLUDE;
+use super::selection::{QuoteFilter, QuotePool};
+use super::{
+    allowed_users, candidate_buttons, collect_button_guesses, fetch_candidates, rating, reply_ephemeral, scoring, QuotePost, RoundRules,
+};
+use crate::GUESSQUOTE_RULES;
+
+const ACCEPT_BUTTON: &str = "duel:accept";
+const DECLINE_BUTTON: &str = "duel:decline";
+/// How long the challenged player has to accept.
+const ACCEPT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Multiplier on the time points of the duellist on serve.
+const SERVE_BONUS: f64 = 1.25;
+
+/// The two sides of a duel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Challenger,
+    Opponent,
+}
+
+/// Who is on serve in a round, counting from 1. The speed bonus alternates so
+/// neither duellist gets it every round, starting with the challenger.
+pub fn serving(round_number: usize) -> Side {
+    if round_number % 2 == 1 {
+        Side::Challenger
+    } else {
+        Side::Opponent
+    }
+}
+
+/// Points of one duellist in a round. Only right answers score, faster ones
+/// more, and the duellist on serve gets their time points boosted.
+pub fn round_points(is_correct: bool, elapsed: Duration, on_serve: bool, rules: &RoundRules) -> i32 {
+    if !is_correct {
+        return 0;
+    }
+    let multiplier = if on_serve { SERVE_BONUS } else { 1.0 };
+    scoring::score_guess(true, elapsed, 0, multiplier, rules).total()
+}
+
+/// The side that won a round, `None` when neither scored more.
+pub fn round_winner(challenger_points: i32, opponent_points: i32) -> Option<Side> {
+    match challenger_points.cmp(&opponent_points) {
+        std::cmp::Ordering::Greater => Some(Side::Challenger),
+        std::cmp::Ordering::Less => Some(Side::Opponent),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+/// Rounds won by each side of a best-of duel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DuelScore {
+    pub challenger: usize,
+    pub opponent: usize,
+}
+
+impl DuelScore {
+    pub fn record(&mut self, winner: Option<Side>) {
+        match winner {
+            Some(Side::Challenger) => self.challenger += 1,
+            Some(Side::Opponent) => self.opponent += 1,
+            None => {}
+        }
+    }
+
+    /// The side that has won a majority of the rounds of a best-of duel.
+    pub fn winner(&self, best_of: usize) -> Option<Side> {
+        let needed = best_of / 2 + 1;
+        if self.challenger >= needed {
+            Some(Side::Challenger)
+        } else if self.opponent >= needed {
+            Some(Side::Opponent)
+        } else {
+            None
+        }
+    }
+
+    /// The side ahead once the round limit is reached, `None` for a draw.
+    pub fn leader(&self) -> Option<Side> {
+        round_winner(self.challenger as i32, self.opponent as i32)
+    }
+}
+
+/// The two duellists and the rules they play by.
+struct Duel {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    challenger_id: UserId,
+    opponent_id: UserId,
+    best_of: usize,
+    rules: RoundRules,
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("duel")
+        .description("Challenge another player to a head-to-head guessquote duel")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::User, "opponent", "The player to challenge")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "best_of", "How many rounds the duel is played over, 5 by default")
+                .add_int_choice("Best of 1", 1)
+                .add_int_choice("Best of 3", 3)
+                .add_int_choice("Best of 5", 5)
+                .add_int_choice("Best of 7", 7),
+        )
+}
+
+/// Runs a duel from the challenge to the final result. Like a guessquote round
+/// it claims the channel for as long as it runs.
+pub async fn handle_commands(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+    games: &GameRegistry,
+    quotes: &QuotePool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = command.guild_id else {
+        return Err("duel command used outside of a guild".into());
+    };
+
+    let options = command.data.options();
+    let opponent: Option<&User> = options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) if option.name == "opponent" => Some(user),
+        _ => None,
+    });
+    let best_of = options.iter()
+        .find_map(|option| match option.value {
+            ResolvedValue::Integer(best_of) if option.name == "best_of" => Some(best_of.max(1) as usize),
+            _ => None,
+        })
+        .unwrap_or(5);
+
+    let Some(opponent) = opponent else {
+        return reply_ephemeral(&ctx, command, "Pick a player to duel.").await;
+    };
+    if opponent.id == command.user.id {
+        return reply_ephemeral(&ctx, command, "You can't duel yourself.").await;
+    }
+    if opponent.bot {
+        return reply_ephemeral(&ctx, command, "Bots don't duel.").await;
+    }
+
+    let challenger_id = command.user.id;
+    let opponent_id = opponent.id;
+    let duel = Duel {
+        guild_id,
+        channel_id: command.channel_id,
+        challenger_id,
+        opponent_id,
+        best_of,
+        rules: GUESSQUOTE_RULES.get().cloned().unwrap_or_default(),
+    };
+    // Drawn rounds don't count, so leave room for some before calling it
+    let max_rounds = best_of * 2;
+
+    let state = RoundState::new(challenger_id, "duel", duel.rules.duration(), max_rounds)
+        .with_players(vec![challenger_id, opponent_id]);
+    let Some(round_guard) = games.try_start(command.channel_id, state) else {
+        return reply_ephemeral(
+            &ctx,
+            command,
+            "A guessquote round is already running in this channel. Check it with `/guessquote status`.",
+        ).await;
+    };
+    let round = round_guard.state();
+
+    info!("Duel challenge in guild {} from {} to {}, best of {}", guild_id, challenger_id, opponent_id, best_of);
+    if !await_acceptance(&ctx, command, round, &duel).await? {
+        return Ok(());
+    }
+
+    let mut score = DuelScore::default();
+    let mut rounds_played = 0;
+    let mut abandoned = false;
+    while score.winner(best_of).is_none() && rounds_played < max_rounds {
+        if rounds_played > 0 {
+            tokio::select! {
+                _ = tokio::time::sleep(INTERLUDE) => {}
+                _ = round.cancelled() => {}
+            }
+            if round.cancelled_by().is_some() {
+                break;
+            }
+            round.start_next_round();
+        }
+        rounds_played += 1;
+
+        let Some(winner) = play_duel_round(&ctx, command, db_pool, quotes, round, &duel, score).await? else {
+            abandoned = round.cancelled_by().is_none();
+            break;
+        };
+        score.record(winner);
+    }
+
+    if let Some(cancelled_by) = round.cancelled_by() {
+        info!("Duel in channel {} cancelled by {}", command.channel_id, cancelled_by);
+        record_duel(db_pool, &duel, score, None, "cancelled").await;
+        command.channel_id
+            .say(&ctx.http, format!("🛑 Duel cancelled by <@{}> at {}-{}, the ratings stay as they were.", cancelled_by, score.challenger, score.opponent))
+            .await?;
+        return Ok(());
+    }
+
+    if abandoned {
+        record_duel(db_pool, &duel, score, None, "abandoned").await;
+        return Ok(());
+    }
+
+    let winner = score.winner(best_of).or_else(|| score.leader());
+    let result = match winner {
+        Some(side) => {
+            let (winner_id, loser_id) = match side {
+                Side::Challenger => (challenger_id, opponent_id),
+                Side::Opponent => (opponent_id, challenger_id),
+            };
+            record_duel(db_pool, &duel, score, Some(winner_id), "finished").await;
+            let rating_lines = update_duel_ratings(db_pool, guild_id, winner_id, loser_id).await;
+            format!(
+                "🏆 <@{}> wins the duel against <@{}>, {}-{}!\n\n📈 **Duel rating:**\n{}",
+                winner_id,
+                loser_id,
+                score.challenger.max(score.opponent),
+                score.challenger.min(score.opponent),
+                rating_lines.join("\n")
+            )
+        }
+        None => {
+            record_duel(db_pool, &duel, score, None, "draw").await;
+            record_draw(db_pool, guild_id, &[challenger_id, opponent_id]).await;
+            format!("🤝 The duel between <@{}> and <@{}> ends in a {}-{} draw.", challenger_id, opponent_id, score.challenger, score.opponent)
+        }
+    };
+
+    command.channel_id.say(&ctx.http, result).await?;
+    Ok(())
+}
+
+/// Posts the challenge and waits for the opponent to accept or decline it.
+async fn await_acceptance(
+    ctx: &serenity::client::Context,
+    command: &CommandInteraction,
+    round: &RoundState,
+    duel: &Duel,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let Duel { challenger_id, opponent_id, best_of, .. } = *duel;
+    let buttons = vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(ACCEPT_BUTTON).label("Accept").style(ButtonStyle::Success),
+        CreateButton::new(DECLINE_BUTTON).label("Decline").style(ButtonStyle::Danger),
+    ])];
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "⚔️ <@{}>, <@{}> challenges you to a best of {} guessquote duel! You have {} seconds to accept.",
+                        opponent_id, challenger_id, best_of, ACCEPT_TIMEOUT.as_secs()
+                    ))
+                    .components(buttons),
+            ),
+        )
+        .await?;
+    let challenge = command.get_response(&ctx.http).await?;
+
+    let mut interactions = ComponentInteractionCollector::new(&ctx.shard)
+        .message_id(challenge.id)
+        .timeout(ACCEPT_TIMEOUT)
+        .stream();
+
+    loop {
+        let interaction = tokio::select! {
+            interaction = interactions.next() => interaction,
+            _ = round.cancelled() => None,
+        };
+        let Some(interaction) = interaction else {
+            break;
+        };
+
+        if interaction.user.id != opponent_id {
+            let response = CreateInteractionResponseMessage::new()
+                .content(format!("Only <@{}> can answer this challenge.", opponent_id))
+                .ephemeral(true);
+            if let Err(e) = interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response)).await {
+                warn!("Error answering duel button: {}", e);
+            }
+            continue;
+        }
+
+        let accepted = interaction.data.custom_id == ACCEPT_BUTTON;
+        let content = if accepted {
+            format!("⚔️ <@{}> accepted the duel against <@{}>, best of {}. First quote coming up!", opponent_id, challenger_id, best_of)
+        } else {
+            format!("<@{}> declined the duel with <@{}>.", opponent_id, challenger_id)
+        };
+        let response = CreateInteractionResponseMessage::new().content(content).components(Vec::new());
+        interaction.create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response)).await?;
+        info!("Duel from {} to {} {}", challenger_id, opponent_id, if accepted { "accepted" } else { "declined" });
+        return Ok(accepted);
+    }
+
+    let content = format!("The duel challenge to <@{}> was not accepted in time.", opponent_id);
+    if let Err(e) = command.edit_response(&ctx.http, EditInteractionResponse::new().content(content).components(Vec::new())).await {
+        warn!("Error expiring duel challenge: {}", e);
+    }
+    Ok(false)
+}
+
+/// Plays one quote of a duel, never one by either duellist, and announces who
+/// took it. Returns `None` when the duel was cancelled or no quote could be found.
+async fn play_duel_round(
+    ctx: &serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+    quotes: &QuotePool,
+    round: &RoundState,
+    duel: &Duel,
+    score: DuelScore,
+) -> Result<Option<Option<Side>>, Box<dyn std::error::Error + Send + Sync>> {
+    let Duel { guild_id, challenger_id, opponent_id, ref rules, .. } = *duel;
+    let number = round.round_number();
+    // The quoted person can't answer about themselves, so a duellist's own quote
+    // would hand the round to the other side.
+    let filter = QuoteFilter {
+        min_length: rules.min_length,
+        difficulty: None,
+        excluded_authors: vec![i64::from(challenger_id), i64::from(opponent_id)],
+    };
+    let Some(quote_id) = quotes.pick(db_pool, guild_id, command.channel_id, &filter).await? else {
+        command.channel_id.say(&ctx.http, "Sorry, I couldn't find a quote for the duel.").await?;
+        return Ok(None);
+    };
+    let (quoted_user_id, name, content) = sqlx::query_as::<_, (i64, String, String)>(
+        "SELECT UserId, Name, Content FROM wdl_database.discord_messages WHERE Id = ?",
+    )
+        .bind(quote_id)
+        .fetch_one(db_pool)
+        .await?;
+
+    round.set_quoted_user(UserId::new(quoted_user_id as u64));
+    let on_serve = serving(number);
+    let server_id = match on_serve {
+        Side::Challenger => challenger_id,
+        Side::Opponent => opponent_id,
+    };
+    let candidates = fetch_candidates(db_pool, quoted_user_id, allowed_users(guild_id)).await;
+    let message = format!(
+        "⚔️ **Duel round {}** · {}-{}\n\n> _{}_\n\nWho said it? You have {} seconds and one answer each. <@{}> is on serve and gets a speed bonus.",
+        number, score.challenger, score.opponent, content, rules.duration_secs, server_id
+    );
+    let quote_post = QuotePost::send(ctx, command, command.channel_id, false, message, candidate_buttons(&candidates, None)).await?;
+
+    let start_time = Instant::now();
+    let guesses = collect_button_guesses(ctx, &quote_post, round, &candidates, quoted_user_id, start_time, rules.duration()).await?;
+    if round.cancelled_by().is_some() {
+        return Ok(None);
+    }
+    let fair_play = fairplay::summary(&round.take_violations());
+
+    let points_of = |side: Side, user_id: UserId| {
+        guesses.iter()
+            .find(|guess| guess.user_id == user_id)
+            .map(|guess| round_points(guess.is_correct, guess.elapsed, on_serve == side, rules))
+            .unwrap_or(0)
+    };
+    let challenger_points = points_of(Side::Challenger, challenger_id);
+    let opponent_points = points_of(Side::Opponent, opponent_id);
+    let winner = round_winner(challenger_points, opponent_points);
+    info!("Duel round {} - challenger: {}, opponent: {}, winner: {:?}", number, challenger_points, opponent_points, winner);
+
+    let outcome = match winner {
+        Some(Side::Challenger) => format!("<@{}> takes the round!", challenger_id),
+        Some(Side::Opponent) => format!("<@{}> takes the round!", opponent_id),
+        None => "Nobody takes this round.".to_string(),
+    };
+    command.channel_id
+        .say(
+            &ctx.http,
+            format!(
+                "The quote was from {}. <@{}>: {} points, <@{}>: {} points. {}{}",
+                name, challenger_id, challenger_points, opponent_id, opponent_points, outcome, fair_play
+            ),
+        )
+        .await?;
+
+    Ok(Some(winner))
+}
+
+/// Stores a played duel. Like round history, failing to write it never stops the game.
+async fn record_duel(db_pool: &MySqlPool, duel: &Duel, score: DuelScore, winner_id: Option<UserId>, outcome: &str) {
+    if let Err(e) = sqlx::query(
+        "INSERT INTO wdl_database.quote_duels
+         (guild_id, channel_id, challenger_id, opponent_id, best_of, challenger_wins, opponent_wins, winner_id, outcome)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+        .bind(i64::from(duel.guild_id))
+        .bind(i64::from(duel.channel_id))
+        .bind(i64::from(duel.challenger_id))
+        .bind(i64::from(duel.opponent_id))
+        .bind(duel.best_of as i32)
+        .bind(score.challenger as i32)
+        .bind(score.opponent as i32)
+        .bind(winner_id.map(i64::from))
+        .bind(outcome)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record duel between {} and {}: {}", duel.challenger_id, duel.opponent_id, e);
+    }
+}
+
+/// Loads a duellist's rating and number of rated duels.
+async fn fetch_duel_rating(db_pool: &MySqlPool, guild_id: GuildId, user_id: UserId) -> (f64, i32) {
+    match sqlx::query_as::<_, (f64, i32)>(
+        "SELECT rating, wins + losses FROM wdl_database.quote_duel_ratings WHERE guild_id = ? AND user_id = ?",
+    )
+        .bind(i64::from(guild_id))
+        .bind(i64::from(user_id))
+        .fetch_optional(db_pool)
+        .await
+    {
+        Ok(Some(rating)) => rating,
+        Ok(None) => (rating::INITIAL_RATING, 0),
+        Err(e) => {
+            warn!("Failed to fetch duel rating of {}: {}", user_id, e);
+            (rating::INITIAL_RATING, 0)
+        }
+    }
+}
+
+/// Moves both duel ratings and win/loss records, returning a line per duellist.
+async fn update_duel_ratings(db_pool: &MySqlPool, guild_id: GuildId, winner_id: UserId, loser_id: UserId) -> Vec<String> {
+    let (winner_rating, winner_duels) = fetch_duel_rating(db_pool, guild_id, winner_id).await;
+    let (loser_rating, loser_duels) = fetch_duel_rating(db_pool, guild_id, loser_id).await;
+    let (winner_change, loser_change) = rating::duel_changes(winner_rating, winner_duels, loser_rating, loser_duels);
+
+    let mut lines = Vec::new();
+    for (user_id, old_rating, change, won) in [
+        (winner_id, winner_rating, winner_change, true),
+        (loser_id, loser_rating, loser_change, false),
+    ] {
+        let new_rating = old_rating + change;
+        info!("Duel rating for user {} in guild {}: {:.1} -> {:.1}", user_id, guild_id, old_rating, new_rating);
+        if let Err(e) = sqlx::query(
+            "INSERT INTO wdl_database.quote_duel_ratings (guild_id, user_id, rating, wins, losses)
+             VALUES (?, ?, ?, ?, ?)
+             ON DUPLICATE KEY UPDATE rating = VALUES(rating), wins = wins + VALUES(wins), losses = losses + VALUES(losses)",
+        )
+            .bind(i64::from(guild_id))
+            .bind(i64::from(user_id))
+            .bind(new_rating)
+            .bind(if won { 1 } else { 0 })
+            .bind(if won { 0 } else { 1 })
+            .execute(db_pool)
+            .await
+        {
+            warn!("Failed to update duel rating for user {}: {}", user_id, e);
+            continue;
+        }
+
+        let change = change.round() as i64;
+        lines.push(format!(
+            "<@{}>: {} → {} ({}{})",
+            user_id,
+            old_rating.round(),
+            new_rating.round(),
+            if change >= 0 { "+" } else { "" },
+            change
+        ));
+    }
+    lines
+}
+
+/// Counts a drawn duel for both duellists, their ratings stay as they are.
+async fn record_draw(db_pool: &MySqlPool, guild_id: GuildId, user_ids: &[UserId]) {
+    for &user_id in user_ids {
+        if let Err(e) = sqlx::query(
+            "INSERT INTO wdl_database.quote_duel_ratings (guild_id, user_id, draws)
+             VALUES (?, ?, 1)
+             ON DUPLICATE KEY UPDATE draws = draws + 1",
+        )
+            .bind(i64::from(guild_id))
+            .bind(i64::from(user_id))
+            .execute(db_pool)
+            .await
+        {
+            warn!("Failed to record duel draw for user {}: {}", user_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serve_alternates() {
+        assert_eq!(serving(1), Side::Challenger);
+        assert_eq!(serving(2), Side::Opponent);
+        assert_eq!(serving(3), Side::Challenger);
+    }
+
+    #[test]
+    fn serve_speeds_up_right_answers_only() {
+        let rules = RoundRules::default();
+        let elapsed = Duration::from_secs(5);
+        assert!(round_points(true, elapsed, true, &rules) > round_points(true, elapsed, false, &rules));
+        assert_eq!(round_points(false, elapsed, true, &rules), 0);
+        assert_eq!(round_winner(0, 0), None);
+        assert_eq!(round_winner(40, 75), Some(Side::Opponent));
+    }
+
+    #[test]
+    fn best_of_needs_a_majority() {
+        let mut score = DuelScore::default();
+        score.record(Some(Side::Challenger));
+        score.record(None);
+        score.record(Some(Side::Opponent));
+        score.record(Some(Side::Challenger));
+        assert_eq!(score.winner(5), None);
+        assert_eq!(score.winner(3), Some(Side::Challenger));
+        assert_eq!(score.leader(), Some(Side::Challenger));
+        assert_eq!(DuelScore { challenger: 1, opponent: 1 }.leader(), None);
+    }
+}
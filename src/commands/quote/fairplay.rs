@@ -0,0 +1,101 @@
+use serenity::all::UserId;
+
+/// A fair-play rule a guess broke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// Bots never play.
+    BotAuthor,
+    /// The quoted author knows their own quote.
+    OwnQuote,
+    /// Naming several people at once is a shotgun guess.
+    SeveralCandidates,
+}
+
+impl Violation {
+    /// Whether the guess is dropped. A shotgun guess still counts, as a wrong one.
+    pub fn drops_guess(self) -> bool {
+        !matches!(self, Violation::SeveralCandidates)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Violation::BotAuthor => "bots can't play, guess ignored",
+            Violation::OwnQuote => "can't answer their own quote, guess ignored",
+            Violation::SeveralCandidates => "named more than one person, counted as wrong",
+        }
+    }
+}
+
+/// Told to players in rounds answered by message. Rounds only collect new
+/// messages, so edits are never seen and need no rule of their own.
+pub const EDITS_IGNORED: &str = "✏️ Edits are ignored, an answer counts as it was first sent.";
+
+/// What the fair-play rules look at in an answer. Edits are never seen, rounds
+/// only collect new messages, so an answer always counts as it was first sent.
+pub struct Answer {
+    pub author_id: UserId,
+    pub is_bot: bool,
+    /// Whether the answer named several candidates.
+    pub names_several: bool,
+}
+
+/// The first rule the answer breaks, if any. `quoted_user` is the author of the
+/// quote being played, when there is one.
+pub fn check(answer: &Answer, quoted_user: Option<UserId>) -> Option<Violation> {
+    if answer.is_bot {
+        Some(Violation::BotAuthor)
+    } else if quoted_user == Some(answer.author_id) {
+        Some(Violation::OwnQuote)
+    } else if answer.names_several {
+        Some(Violation::SeveralCandidates)
+    } else {
+        None
+    }
+}
+
+/// The round summary section listing every rule that was broken, one line per
+/// player and rule. Empty when everyone played fair.
+pub fn summary(violations: &[(UserId, Violation)]) -> String {
+    if violations.is_empty() {
+        return String::new();
+    }
+
+    let mut section = "\n⚖️ **Fair play:**\n".to_string();
+    for (user_id, violation) in violations {
+        section.push_str(&format!("<@{}>: {}\n", user_id, violation.describe()));
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(author_id: u64) -> Answer {
+        Answer { author_id: UserId::new(author_id), is_bot: false, names_several: false }
+    }
+
+    #[test]
+    fn fair_answers_pass() {
+        assert_eq!(check(&answer(1), Some(UserId::new(2))), None);
+        assert_eq!(check(&answer(1), None), None);
+    }
+
+    #[test]
+    fn each_rule_is_enforced() {
+        assert_eq!(check(&Answer { is_bot: true, ..answer(1) }, None), Some(Violation::BotAuthor));
+        assert_eq!(check(&answer(2), Some(UserId::new(2))), Some(Violation::OwnQuote));
+        assert_eq!(check(&Answer { names_several: true, ..answer(1) }, None), Some(Violation::SeveralCandidates));
+    }
+
+    #[test]
+    fn only_shotgun_guesses_still_count() {
+        assert!(!Violation::SeveralCandidates.drops_guess());
+        assert!(Violation::OwnQuote.drops_guess());
+        assert_eq!(
+            summary(&[(UserId::new(5), Violation::OwnQuote)]),
+            "\n⚖️ **Fair play:**\n<@5>: can't answer their own quote, guess ignored\n"
+        );
+        assert_eq!(summary(&[]), "");
+    }
+}
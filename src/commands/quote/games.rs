@@ -0,0 +1,316 @@
+use log::info;
+use serenity::all::{ChannelId, CommandOptionType, CreateCommandOption, ResolvedOption, ResolvedValue, UserId};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use super::fairplay::{self, Violation};
+
+/// The ways a round can be played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    /// Name the author in a message.
+    Text,
+    /// Pick the author from buttons.
+    Buttons,
+    /// Guess the year or month the quote was said in.
+    When,
+    /// Type the words missing from the quote.
+    Blanks,
+    /// Vote whether the quote is real or made up from the author's messages.
+    RealOrFake,
+    /// Pick which of several quotes the named author said.
+    Reverse,
+}
+
+impl GameMode {
+    pub fn option() -> CreateCommandOption {
+        CreateCommandOption::new(CommandOptionType::String, "mode", "How players answer")
+            .add_string_choice("Free text (mention or name)", "text")
+            .add_string_choice("Multiple choice buttons", "buttons")
+            .add_string_choice("When was it said? (year or month)", "when")
+            .add_string_choice("Finish the quote (fill in the blanks)", "finish")
+            .add_string_choice("Real or fake? (spot the impostor quote)", "realfake")
+            .add_string_choice("Reverse (pick which quote they said)", "reverse")
+    }
+
+    /// Reads the `mode` option, free text is the default.
+    pub fn from_options(options: &[ResolvedOption]) -> Self {
+        options.iter()
+            .find_map(|option| match option.value {
+                ResolvedValue::String("buttons") if option.name == "mode" => Some(GameMode::Buttons),
+                ResolvedValue::String("when") if option.name == "mode" => Some(GameMode::When),
+                ResolvedValue::String("finish") if option.name == "mode" => Some(GameMode::Blanks),
+                ResolvedValue::String("realfake") if option.name == "mode" => Some(GameMode::RealOrFake),
+                ResolvedValue::String("reverse") if option.name == "mode" => Some(GameMode::Reverse),
+                _ => None,
+            })
+            .unwrap_or(GameMode::Text)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Text => "text",
+            GameMode::Buttons => "buttons",
+            GameMode::When => "when",
+            GameMode::Blanks => "finish",
+            GameMode::RealOrFake => "realfake",
+            GameMode::Reverse => "reverse",
+        }
+    }
+
+    /// Whether players answer by sending a message rather than with buttons.
+    pub fn answered_by_message(self) -> bool {
+        matches!(self, GameMode::Text | GameMode::When | GameMode::Blanks)
+    }
+
+    /// Whether players answer who said the quote, which is what ratings and
+    /// quote difficulty are based on.
+    pub fn asks_who(self) -> bool {
+        matches!(self, GameMode::Text | GameMode::Buttons)
+    }
+}
+
+/// How a player has answered in the current round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Answers {
+    /// How far into the round the first answer came, and whether it was right.
+    /// Wagers are settled on it, whatever the player answers later.
+    pub first_after: Duration,
+    pub first_correct: bool,
+    /// Whether any answer so far was right.
+    pub solved: bool,
+}
+
+/// Shared bookkeeping for a round or match that is currently running in a channel.
+pub struct RoundState {
+    pub started_by: UserId,
+    pub mode: &'static str,
+    pub duration: Duration,
+    /// How many quotes the game has, more than one makes it a match.
+    pub rounds: usize,
+    round_number: AtomicUsize,
+    round_started_at: Mutex<Instant>,
+    /// The only players allowed to answer, everyone may when empty.
+    players: Vec<UserId>,
+    /// The author of the quote being played, who can't answer it.
+    quoted_user: Mutex<Option<UserId>>,
+    /// Answers that broke a fair-play rule since the round started.
+    violations: Mutex<Vec<(UserId, Violation)>>,
+    guesses: AtomicUsize,
+    answers: Mutex<HashMap<UserId, Answers>>,
+    cancelled_by: watch::Sender<Option<UserId>>,
+}
+
+impl RoundState {
+    pub fn new(started_by: UserId, mode: &'static str, duration: Duration, rounds: usize) -> Self {
+        RoundState {
+            started_by,
+            mode,
+            duration,
+            rounds,
+            round_number: AtomicUsize::new(1),
+            round_started_at: Mutex::new(Instant::now()),
+            players: Vec::new(),
+            quoted_user: Mutex::new(None),
+            violations: Mutex::new(Vec::new()),
+            guesses: AtomicUsize::new(0),
+            answers: Mutex::new(HashMap::new()),
+            cancelled_by: watch::channel(None).0,
+        }
+    }
+
+    /// Restricts answering to the given players, like the two sides of a duel.
+    pub fn with_players(mut self, players: Vec<UserId>) -> Self {
+        self.players = players;
+        self
+    }
+
+    pub fn may_answer(&self, user_id: UserId) -> bool {
+        self.players.is_empty() || self.players.contains(&user_id)
+    }
+
+    /// Whether every allowed player has answered, which only happens when the
+    /// round is restricted to a few players.
+    pub fn all_answered(&self, answered: usize) -> bool {
+        !self.players.is_empty() && answered >= self.players.len()
+    }
+
+    pub fn set_quoted_user(&self, user_id: UserId) {
+        *self.quoted_user.lock().unwrap_or_else(|e| e.into_inner()) = Some(user_id);
+    }
+
+    /// The author of the quote currently being asked, once the quote is picked.
+    pub fn quoted_user(&self) -> Option<UserId> {
+        *self.quoted_user.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Checks an answer against the fair-play rules. A broken rule is logged and
+    /// kept for the round summary, once per player and rule.
+    pub fn check_fair_play(&self, answer: &fairplay::Answer) -> Option<Violation> {
+        let violation = fairplay::check(answer, self.quoted_user())?;
+        info!("Fair play: answer from {} broke rule {:?}", answer.author_id, violation);
+
+        let mut violations = self.violations.lock().unwrap_or_else(|e| e.into_inner());
+        if !violations.contains(&(answer.author_id, violation)) {
+            violations.push((answer.author_id, violation));
+        }
+        Some(violation)
+    }
+
+    /// The fair-play violations of the round that just ended, clearing them for the next one.
+    pub fn take_violations(&self) -> Vec<(UserId, Violation)> {
+        std::mem::take(&mut *self.violations.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    pub fn remaining(&self) -> Duration {
+        let started_at = *self.round_started_at.lock().unwrap_or_else(|e| e.into_inner());
+        self.duration.saturating_sub(started_at.elapsed())
+    }
+
+    /// The quote of the match currently being played, counting from 1.
+    pub fn round_number(&self) -> usize {
+        self.round_number.load(Ordering::Relaxed)
+    }
+
+    /// Moves a match on to its next quote and restarts the round timer.
+    pub fn start_next_round(&self) {
+        self.round_number.fetch_add(1, Ordering::Relaxed);
+        *self.round_started_at.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        self.answers.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Counts a guess and remembers the player's first answer of the round.
+    pub fn record_guess(&self, user_id: UserId, elapsed: Duration, is_correct: bool) {
+        self.guesses.fetch_add(1, Ordering::Relaxed);
+        let mut answers = self.answers.lock().unwrap_or_else(|e| e.into_inner());
+        let answers = answers.entry(user_id).or_insert(Answers { first_after: elapsed, first_correct: is_correct, solved: false });
+        answers.solved |= is_correct;
+    }
+
+    /// How the player has answered in the current round, `None` before their first answer.
+    pub fn answers_of(&self, user_id: UserId) -> Option<Answers> {
+        self.answers.lock().unwrap_or_else(|e| e.into_inner()).get(&user_id).copied()
+    }
+
+    pub fn guess_count(&self) -> usize {
+        self.guesses.load(Ordering::Relaxed)
+    }
+
+    /// The user who cancelled the round, if it has been cancelled.
+    pub fn cancelled_by(&self) -> Option<UserId> {
+        *self.cancelled_by.borrow()
+    }
+
+    /// Resolves once the round has been cancelled.
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancelled_by.subscribe();
+        let _ = receiver.wait_for(|cancelled_by| cancelled_by.is_some()).await;
+    }
+}
+
+/// Tracks which channels have a guessquote round running, so that only one
+/// collector ever listens to a channel at a time.
+#[derive(Clone, Default)]
+pub struct GameRegistry {
+    rounds: Arc<Mutex<HashMap<ChannelId, Arc<RoundState>>>>,
+}
+
+impl GameRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claims the channel for a new round. Returns `None` when a round is already
+    /// running there. The claim is released when the returned guard is dropped,
+    /// which also happens when the round errors out or panics.
+    pub fn try_start(&self, channel_id: ChannelId, state: RoundState) -> Option<RoundGuard> {
+        let mut rounds = self.rounds.lock().unwrap_or_else(|e| e.into_inner());
+        if rounds.contains_key(&channel_id) {
+            return None;
+        }
+
+        let state = Arc::new(state);
+        rounds.insert(channel_id, Arc::clone(&state));
+        info!("Registered guessquote round in channel {}", channel_id);
+
+        Some(RoundGuard {
+            registry: self.clone(),
+            channel_id,
+            state,
+        })
+    }
+
+    pub fn get(&self, channel_id: ChannelId) -> Option<Arc<RoundState>> {
+        let rounds = self.rounds.lock().unwrap_or_else(|e| e.into_inner());
+        rounds.get(&channel_id).cloned()
+    }
+
+    /// Signals the running round in the channel to stop. Returns `false` when
+    /// there is nothing to cancel.
+    pub fn cancel(&self, channel_id: ChannelId, cancelled_by: UserId) -> bool {
+        match self.get(channel_id) {
+            Some(state) => {
+                state.cancelled_by.send_if_modified(|current| {
+                    if current.is_none() {
+                        *current = Some(cancelled_by);
+                        true
+                    } else {
+                        false
+                    }
+                });
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps a channel claimed for as long as the round is alive.
+pub struct RoundGuard {
+    registry: GameRegistry,
+    channel_id: ChannelId,
+    state: Arc<RoundState>,
+}
+
+impl RoundGuard {
+    pub fn state(&self) -> &RoundState {
+        &self.state
+    }
+}
+
+impl Drop for RoundGuard {
+    fn drop(&mut self) {
+        let mut rounds = self.registry.rounds.lock().unwrap_or_else(|e| e.into_inner());
+        // Only remove our own entry, never a round that replaced it
+        if rounds.get(&self.channel_id).is_some_and(|state| Arc::ptr_eq(state, &self.state)) {
+            rounds.remove(&self.channel_id);
+            info!("Released guessquote round in channel {}", self.channel_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_first_answer_sticks() {
+        let round = RoundState::new(UserId::new(1), "finish", Duration::from_secs(30), 2);
+        let player = UserId::new(2);
+        assert_eq!(round.answers_of(player), None);
+
+        round.record_guess(player, Duration::from_secs(4), false);
+        round.record_guess(player, Duration::from_secs(9), true);
+        assert_eq!(
+            round.answers_of(player),
+            Some(Answers { first_after: Duration::from_secs(4), first_correct: false, solved: true })
+        );
+        assert_eq!(round.guess_count(), 2);
+
+        round.start_next_round();
+        assert_eq!(round.answers_of(player), None);
+    }
+}
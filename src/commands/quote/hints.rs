@@ -0,0 +1,260 @@
+use chrono::{DateTime, Datelike, Utc};
+use log::{info, warn};
+use serenity::all::{
+    ButtonStyle, ComponentInteractionCollector, CreateActionRow, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, MessageId,
+};
+use serenity::futures::StreamExt;
+use sqlx::MySqlPool;
+use std::time::Instant;
+
+use super::games::{GameMode, RoundState};
+use super::{reverse, RoundRules};
+
+pub const HINT_BUTTON_PREFIX: &str = "guessquote-hint:";
+
+/// Something players can reveal about the quote during a round, at the cost of
+/// the round's maximum points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    Year,
+    Initial,
+    Before,
+    After,
+}
+
+impl Hint {
+    fn id(self) -> &'static str {
+        match self {
+            Hint::Year => "year",
+            Hint::Initial => "initial",
+            Hint::Before => "before",
+            Hint::After => "after",
+        }
+    }
+
+    fn from_custom_id(custom_id: &str) -> Option<Self> {
+        let id = custom_id.strip_prefix(HINT_BUTTON_PREFIX)?;
+        [Hint::Year, Hint::Initial, Hint::Before, Hint::After].into_iter().find(|hint| hint.id() == id)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Hint::Year => "📅 Year",
+            Hint::Initial => "🔤 Initial",
+            Hint::Before => "⬆️ Message before",
+            Hint::After => "⬇️ Message after",
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Hint::Year => "the year",
+            Hint::Initial => "the author's initial",
+            Hint::Before => "the message before",
+            Hint::After => "the message after",
+        }
+    }
+}
+
+/// The hints a mode offers. Hints that would give the answer away, or say
+/// nothing new, are left out.
+pub fn available(mode: GameMode) -> &'static [Hint] {
+    match mode {
+        GameMode::Text | GameMode::Buttons => &[Hint::Year, Hint::Initial, Hint::Before, Hint::After],
+        GameMode::When | GameMode::Blanks => &[Hint::Before, Hint::After],
+        GameMode::RealOrFake | GameMode::Reverse => &[],
+    }
+}
+
+/// The button row offering the hints, with what each one costs.
+pub fn buttons(hints: &[Hint], rules: &RoundRules) -> Option<CreateActionRow> {
+    if hints.is_empty() {
+        return None;
+    }
+
+    let buttons = hints.iter()
+        .map(|hint| {
+            CreateButton::new(format!("{}{}", HINT_BUTTON_PREFIX, hint.id()))
+                .label(format!("{} (-{})", hint.label(), rules.hint_cost))
+                .style(ButtonStyle::Primary)
+        })
+        .collect();
+    Some(CreateActionRow::Buttons(buttons))
+}
+
+/// The most a guess can earn once `hints_used` hints are out, never below the
+/// points of an answer at the buzzer.
+pub fn max_points(rules: &RoundRules, hints_used: usize) -> i32 {
+    (rules.max_points - rules.hint_cost * hints_used as i32).max(rules.min_points)
+}
+
+/// The first letter of the author's name.
+fn initial(name: &str) -> Option<char> {
+    name.chars().find(|c| c.is_alphanumeric()).and_then(|c| c.to_uppercase().next())
+}
+
+/// The line of the round results naming the hints that were used.
+pub fn summary(used: &[Hint], rules: &RoundRules) -> String {
+    if used.is_empty() {
+        return String::new();
+    }
+
+    let names: Vec<&str> = used.iter().map(|hint| hint.describe()).collect();
+    format!("💡 Hints used: {}. Max points were {}.\n\n", names.join(", "), max_points(rules, used.len()))
+}
+
+/// What the hints reveal about the quote of a round.
+pub struct HintedQuote<'a> {
+    pub guild_id: i64,
+    /// The channel the quote was originally said in.
+    pub channel_id: i64,
+    pub said_at: DateTime<Utc>,
+    pub author_name: &'a str,
+    pub hints: &'a [Hint],
+}
+
+/// The message said right before or after the quote in its channel.
+async fn neighbour(db_pool: &MySqlPool, quote: &HintedQuote<'_>, before: bool) -> Result<Option<String>, sqlx::Error> {
+    let query = if before {
+        "SELECT Content FROM wdl_database.discord_messages
+         WHERE GuildId = ? AND ChannelId = ? AND Timestamp < ?
+         ORDER BY Timestamp DESC LIMIT 1"
+    } else {
+        "SELECT Content FROM wdl_database.discord_messages
+         WHERE GuildId = ? AND ChannelId = ? AND Timestamp > ?
+         ORDER BY Timestamp ASC LIMIT 1"
+    };
+
+    let row = sqlx::query_as::<_, (String,)>(query)
+        .bind(quote.guild_id)
+        .bind(quote.channel_id)
+        .bind(quote.said_at)
+        .fetch_optional(db_pool)
+        .await?;
+    Ok(row.map(|(content,)| content))
+}
+
+async fn reveal(db_pool: &MySqlPool, hint: Hint, quote: &HintedQuote<'_>) -> String {
+    match hint {
+        Hint::Year => format!("it was said in **{}**.", quote.said_at.year()),
+        Hint::Initial => match initial(quote.author_name) {
+            Some(initial) => format!("the author's name starts with **{}**.", initial),
+            None => "the author's name has no letters to give away.".to_string(),
+        },
+        Hint::Before | Hint::After => {
+            let position = if hint == Hint::Before { "before" } else { "after" };
+            match neighbour(db_pool, quote, hint == Hint::Before).await {
+                Ok(Some(content)) => format!("the message {} it was:\n> _{}_", position, reverse::preview(&content)),
+                Ok(None) => format!("there is no message {} it in the archive.", position),
+                Err(e) => {
+                    warn!("Failed to fetch the message {} the quote: {}", position, e);
+                    format!("the message {} it couldn't be loaded.", position)
+                }
+            }
+        }
+    }
+}
+
+/// Answers hint buttons on the round message until the round ends. Every hint
+/// can be bought once per round and is shown to everyone. Returns the hints
+/// used, in the order they were bought.
+pub async fn serve(
+    ctx: &serenity::client::Context,
+    db_pool: &MySqlPool,
+    round: &RoundState,
+    message_id: MessageId,
+    quote: &HintedQuote<'_>,
+    start_time: Instant,
+    rules: &RoundRules,
+) -> Vec<Hint> {
+    let mut used = Vec::new();
+
+    let remaining = rules.duration().saturating_sub(start_time.elapsed());
+    let mut interactions = ComponentInteractionCollector::new(&ctx.shard)
+        .message_id(message_id)
+        .filter(|interaction| interaction.data.custom_id.starts_with(HINT_BUTTON_PREFIX))
+        .timeout(remaining)
+        .stream();
+
+    loop {
+        let interaction = tokio::select! {
+            interaction = interactions.next() => interaction,
+            _ = round.cancelled() => None,
+        };
+        let Some(interaction) = interaction else {
+            break;
+        };
+
+        // A hint would only help the one who said the quote or who already has the answer
+        let user_id = interaction.user.id;
+        let response = match Hint::from_custom_id(&interaction.data.custom_id) {
+            Some(_) if round.quoted_user() == Some(user_id) => {
+                CreateInteractionResponseMessage::new().content("You said this one, no hints for you.").ephemeral(true)
+            }
+            Some(_) if round.answers_of(user_id).is_some_and(|answers| answers.solved) => {
+                CreateInteractionResponseMessage::new()
+                    .content("You already got this one, hints are for those still guessing.")
+                    .ephemeral(true)
+            }
+            Some(hint) if quote.hints.contains(&hint) && !used.contains(&hint) => {
+                used.push(hint);
+                info!("User {} bought the {:?} hint, {} hints used", user_id, hint, used.len());
+                let content = format!(
+                    "💡 <@{}> bought a hint: {}\n-# This round is now worth at most {} points.",
+                    user_id,
+                    reveal(db_pool, hint, quote).await,
+                    max_points(rules, used.len())
+                );
+                CreateInteractionResponseMessage::new().content(content)
+            }
+            Some(_) => CreateInteractionResponseMessage::new().content("That hint is already out.").ephemeral(true),
+            None => {
+                warn!("Ignoring unknown hint component: {}", interaction.data.custom_id);
+                continue;
+            }
+        };
+
+        if let Err(e) = interaction.create_response(&ctx.http, CreateInteractionResponse::Message(response)).await {
+            warn!("Error answering hint button: {}", e);
+        }
+    }
+
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_lower_max_points_down_to_min() {
+        let rules = RoundRules { max_points: 100, min_points: 10, hint_cost: 20, ..RoundRules::default() };
+        assert_eq!(max_points(&rules, 0), 100);
+        assert_eq!(max_points(&rules, 2), 60);
+        assert_eq!(max_points(&rules, 5), 10);
+    }
+
+    #[test]
+    fn button_ids_round_trip() {
+        for &hint in available(GameMode::Text) {
+            assert_eq!(Hint::from_custom_id(&format!("{}{}", HINT_BUTTON_PREFIX, hint.id())), Some(hint));
+        }
+        assert_eq!(Hint::from_custom_id("guessquote:12"), None);
+        assert!(available(GameMode::Reverse).is_empty());
+        assert!(!available(GameMode::When).contains(&Hint::Year));
+    }
+
+    #[test]
+    fn initial_and_summary() {
+        assert_eq!(initial("_kevin"), Some('K'));
+        assert_eq!(initial("🙂"), None);
+
+        let rules = RoundRules { max_points: 100, min_points: 10, hint_cost: 20, ..RoundRules::default() };
+        assert_eq!(summary(&[], &rules), "");
+        assert_eq!(
+            summary(&[Hint::Year, Hint::Before], &rules),
+            "💡 Hints used: the year, the message before. Max points were 60.\n\n"
+        );
+    }
+}
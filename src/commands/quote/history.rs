@@ -0,0 +1,98 @@
+use log::{info, warn};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use super::{Guess, RoundRules};
+
+/// Identifies a round in `quote_rounds` and ties its guesses together.
+pub struct RoundRecord {
+    pub id: String,
+    pub message_id: i64,
+    /// Whether the round made it into `quote_rounds`, rows referencing it can only
+    /// be written when it did.
+    pub recorded: bool,
+}
+
+/// Where a round is played and what it is about.
+pub struct NewRound<'a> {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub started_by: i64,
+    pub message_id: i64,
+    pub quoted_user_id: i64,
+    pub mode: &'a str,
+    /// The match the round is part of, if any.
+    pub match_id: Option<&'a str>,
+}
+
+/// Stores the round metadata when a round starts. Failing to write history never
+/// stops the game, so errors are only logged.
+pub async fn record_round_start(db_pool: &MySqlPool, round: NewRound<'_>, rules: &RoundRules) -> RoundRecord {
+    let mut record = RoundRecord {
+        id: Uuid::new_v4().to_string(),
+        message_id: round.message_id,
+        recorded: false,
+    };
+
+    if let Err(e) = sqlx::query(
+        "INSERT INTO wdl_database.quote_rounds
+         (id, guild_id, channel_id, started_by, message_id, quoted_user_id, mode, duration_secs, min_length, hardcore, match_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+        .bind(&record.id)
+        .bind(round.guild_id)
+        .bind(round.channel_id)
+        .bind(round.started_by)
+        .bind(round.message_id)
+        .bind(round.quoted_user_id)
+        .bind(round.mode)
+        .bind(rules.duration_secs)
+        .bind(rules.min_length)
+        .bind(rules.hardcore)
+        .bind(round.match_id)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record round {}: {}", record.id, e);
+    } else {
+        info!("Recorded start of round {}", record.id);
+        record.recorded = true;
+    }
+
+    record
+}
+
+/// Stores a single guess along with the points it was awarded.
+pub async fn record_guess(db_pool: &MySqlPool, round: &RoundRecord, guess: &Guess, points_awarded: i32) {
+    if let Err(e) = sqlx::query(
+        "INSERT INTO wdl_database.quote_guesses
+         (round_id, message_id, guesser_id, guessed_user_id, correct, latency_ms, points_awarded)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+        .bind(&round.id)
+        .bind(round.message_id)
+        .bind(i64::from(guess.user_id))
+        .bind(guess.guessed_user_id)
+        .bind(guess.is_correct)
+        .bind(guess.elapsed.as_millis() as i64)
+        .bind(points_awarded)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record guess from {} in round {}: {}", guess.user_id, round.id, e);
+    }
+}
+
+/// Marks the round as finished, or cancelled when it was stopped early.
+pub async fn record_round_end(db_pool: &MySqlPool, round: &RoundRecord, cancelled: bool) {
+    if let Err(e) = sqlx::query(
+        "UPDATE wdl_database.quote_rounds SET ended_at = CURRENT_TIMESTAMP, cancelled = ? WHERE id = ?",
+    )
+        .bind(cancelled)
+        .bind(&round.id)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record end of round {}: {}", round.id, e);
+    }
+}
@@ -0,0 +1,694 @@
+use log::{info, warn};
+use serenity::all::{
+    CommandInteraction, CommandOptionType, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, GuildId, Permissions, ResolvedOption, ResolvedValue,
+};
+use sqlx::{MySql, MySqlPool, Transaction};
+use std::collections::HashSet;
+
+use super::{difficulty, seasons};
+
+/// Round ids are UUIDs, admins may shorten them to a prefix of this length.
+const MIN_ROUND_PREFIX: usize = 8;
+/// Largest number of points a single adjustment may give or take.
+const MAX_ADJUSTMENT: i64 = 100_000;
+/// Ledger entries shown by the history subcommand, with reasons cut short so
+/// they fit in one message.
+const HISTORY_ENTRIES: i64 = 10;
+const HISTORY_REASON_CHARS: usize = 80;
+
+/// What a ledger entry was booked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Guess,
+    Daily,
+    Wager,
+    Adjustment,
+    Reversal,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Guess => "guess",
+            Kind::Daily => "daily",
+            Kind::Wager => "wager",
+            Kind::Adjustment => "adjustment",
+            Kind::Reversal => "reversal",
+        }
+    }
+}
+
+/// A change to a player's points. Entries are only ever appended, a mistake is
+/// corrected with a new entry.
+pub struct Entry<'a> {
+    guild_id: GuildId,
+    user_id: i64,
+    /// The season the entry also counts for.
+    season_id: Option<i32>,
+    kind: Kind,
+    points: i32,
+    /// Whether the answer was right, for entries that count as an attempt.
+    outcome: Option<bool>,
+    round_id: Option<&'a str>,
+    reason: Option<&'a str>,
+    created_by: Option<i64>,
+}
+
+impl<'a> Entry<'a> {
+    fn new(guild_id: GuildId, user_id: i64, kind: Kind, points: i32) -> Self {
+        Entry { guild_id, user_id, season_id: None, kind, points, outcome: None, round_id: None, reason: None, created_by: None }
+    }
+
+    pub fn guess(guild_id: GuildId, user_id: i64, season_id: Option<i32>, round_id: &'a str, is_correct: bool, points: i32) -> Self {
+        Entry {
+            season_id,
+            outcome: Some(is_correct),
+            round_id: Some(round_id),
+            ..Entry::new(guild_id, user_id, Kind::Guess, points)
+        }
+    }
+
+    /// Daily challenges keep their own streaks, so a daily result only moves
+    /// points and leaves the round counters and streaks alone.
+    pub fn daily(guild_id: GuildId, user_id: i64, season_id: Option<i32>, points: i32) -> Self {
+        Entry { season_id, ..Entry::new(guild_id, user_id, Kind::Daily, points) }
+    }
+
+    pub fn wager(guild_id: GuildId, user_id: i64, season_id: Option<i32>, round_id: &'a str, payout: i32) -> Self {
+        Entry { season_id, round_id: Some(round_id), ..Entry::new(guild_id, user_id, Kind::Wager, payout) }
+    }
+
+    fn adjustment(guild_id: GuildId, user_id: i64, season_id: Option<i32>, points: i32, reason: &'a str, created_by: i64) -> Self {
+        Entry {
+            season_id,
+            reason: Some(reason),
+            created_by: Some(created_by),
+            ..Entry::new(guild_id, user_id, Kind::Adjustment, points)
+        }
+    }
+}
+
+/// A ledger row as far as the totals are concerned.
+#[derive(Debug, Clone, Copy, Default)]
+struct Row {
+    id: i64,
+    points: i32,
+    attempts: i32,
+    correct: i32,
+    /// Streaks carried over by an opening balance.
+    streak: Option<i32>,
+    best_streak: Option<i32>,
+    reverses_id: Option<i64>,
+}
+
+/// A player's totals as stored in `quote_scores` and `quote_season_scores`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub points: i32,
+    pub attempts: i32,
+    pub correct: i32,
+    pub current_streak: i32,
+    pub best_streak: i32,
+}
+
+/// Adds a row to the totals. Rows that take part in a reversal only move the
+/// counts, they leave the streaks alone.
+fn apply(totals: &mut Totals, row: &Row, reversed: bool) {
+    totals.points += row.points;
+    totals.attempts += row.attempts;
+    totals.correct += row.correct;
+    if reversed || row.reverses_id.is_some() {
+        return;
+    }
+
+    if let Some(streak) = row.streak {
+        totals.current_streak = streak;
+        totals.best_streak = totals.best_streak.max(row.best_streak.unwrap_or(streak));
+    } else if row.attempts > 0 {
+        totals.current_streak = if row.correct > 0 { totals.current_streak + 1 } else { 0 };
+        totals.best_streak = totals.best_streak.max(totals.current_streak);
+    }
+}
+
+/// Adds up a player's ledger rows, oldest first. Reversed entries and their
+/// reversals cancel out and are left out of the streaks.
+fn fold(rows: &[Row]) -> Totals {
+    let reversed: HashSet<i64> = rows.iter().filter_map(|row| row.reverses_id).collect();
+
+    let mut totals = Totals::default();
+    for row in rows {
+        apply(&mut totals, row, reversed.contains(&row.id));
+    }
+    totals
+}
+
+/// Round ids are UUIDs, so a prefix of one only holds hex digits and dashes.
+fn valid_round_prefix(prefix: &str) -> bool {
+    prefix.len() >= MIN_ROUND_PREFIX && prefix.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+/// Totals a ledger scope adds up to, the guild's all-time scores or a season.
+#[derive(Clone, Copy)]
+pub enum Scope {
+    Guild(GuildId),
+    Season(i32),
+}
+
+async fn fetch_rows(transaction: &mut Transaction<'_, MySql>, scope: Scope, user_id: i64) -> Result<Vec<Row>, sqlx::Error> {
+    // Season opening balances only count for their season
+    let (query, scope_id) = match scope {
+        Scope::Guild(guild_id) => (
+            "SELECT id, points, attempts, correct, streak, best_streak, reverses_id
+             FROM wdl_database.quote_points_ledger
+             WHERE guild_id = ? AND user_id = ? AND kind <> 'season_opening'
+             ORDER BY id",
+            i64::from(guild_id),
+        ),
+        Scope::Season(season_id) => (
+            "SELECT id, points, attempts, correct, streak, best_streak, reverses_id
+             FROM wdl_database.quote_points_ledger
+             WHERE season_id = ? AND user_id = ?
+             ORDER BY id",
+            i64::from(season_id),
+        ),
+    };
+
+    let rows = sqlx::query_as::<_, (i64, i32, i32, i32, Option<i32>, Option<i32>, Option<i64>)>(query)
+        .bind(scope_id)
+        .bind(user_id)
+        .fetch_all(&mut **transaction)
+        .await?;
+    Ok(rows.into_iter()
+        .map(|(id, points, attempts, correct, streak, best_streak, reverses_id)| Row {
+            id, points, attempts, correct, streak, best_streak, reverses_id,
+        })
+        .collect())
+}
+
+/// The player's stored totals in the scope, locked until the transaction ends.
+pub async fn fetch_totals(transaction: &mut Transaction<'_, MySql>, scope: Scope, user_id: i64) -> Result<Totals, sqlx::Error> {
+    let (query, scope_id) = match scope {
+        Scope::Guild(guild_id) => (
+            "SELECT points, total_attempts, correct_guesses, current_streak, best_streak
+             FROM wdl_database.quote_scores
+             WHERE guild_id = ? AND user_id = ?
+             FOR UPDATE",
+            i64::from(guild_id),
+        ),
+        Scope::Season(season_id) => (
+            "SELECT points, total_attempts, correct_guesses, current_streak, best_streak
+             FROM wdl_database.quote_season_scores
+             WHERE season_id = ? AND user_id = ?
+             FOR UPDATE",
+            i64::from(season_id),
+        ),
+    };
+
+    let row = sqlx::query_as::<_, (i32, i32, i32, i32, i32)>(query)
+        .bind(scope_id)
+        .bind(user_id)
+        .fetch_optional(&mut **transaction)
+        .await?;
+    Ok(row
+        .map(|(points, attempts, correct, current_streak, best_streak)| Totals { points, attempts, correct, current_streak, best_streak })
+        .unwrap_or_default())
+}
+
+/// Rewrites a player's totals in the scope from the whole ledger.
+async fn refresh(transaction: &mut Transaction<'_, MySql>, scope: Scope, user_id: i64) -> Result<Totals, sqlx::Error> {
+    let totals = fold(&fetch_rows(transaction, scope, user_id).await?);
+    store(transaction, scope, user_id, totals).await?;
+    Ok(totals)
+}
+
+/// Adds a newly booked row to a player's stored totals in the scope.
+async fn add_row(transaction: &mut Transaction<'_, MySql>, scope: Scope, user_id: i64, row: &Row) -> Result<Totals, sqlx::Error> {
+    let mut totals = fetch_totals(transaction, scope, user_id).await?;
+    apply(&mut totals, row, false);
+    store(transaction, scope, user_id, totals).await?;
+    Ok(totals)
+}
+
+async fn store(transaction: &mut Transaction<'_, MySql>, scope: Scope, user_id: i64, totals: Totals) -> Result<(), sqlx::Error> {
+    let (query, scope_id) = match scope {
+        Scope::Guild(guild_id) => (
+            "INSERT INTO wdl_database.quote_scores
+             (guild_id, user_id, correct_guesses, total_attempts, points, current_streak, best_streak)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON DUPLICATE KEY UPDATE
+             correct_guesses = VALUES(correct_guesses),
+             total_attempts = VALUES(total_attempts),
+             points = VALUES(points),
+             current_streak = VALUES(current_streak),
+             best_streak = VALUES(best_streak)",
+            i64::from(guild_id),
+        ),
+        Scope::Season(season_id) => (
+            "INSERT INTO wdl_database.quote_season_scores
+             (season_id, user_id, correct_guesses, total_attempts, points, current_streak, best_streak)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON DUPLICATE KEY UPDATE
+             correct_guesses = VALUES(correct_guesses),
+             total_attempts = VALUES(total_attempts),
+             points = VALUES(points),
+             current_streak = VALUES(current_streak),
+             best_streak = VALUES(best_streak)",
+            i64::from(season_id),
+        ),
+    };
+
+    sqlx::query(query)
+        .bind(scope_id)
+        .bind(user_id)
+        .bind(totals.correct)
+        .bind(totals.attempts)
+        .bind(totals.points)
+        .bind(totals.current_streak)
+        .bind(totals.best_streak)
+        .execute(&mut **transaction)
+        .await?;
+    Ok(())
+}
+
+/// The player's current streak, locking their totals until the transaction ends
+/// so the streak can't change before the guess building on it is booked.
+pub async fn current_streak(transaction: &mut Transaction<'_, MySql>, guild_id: GuildId, user_id: i64) -> Result<i32, sqlx::Error> {
+    let row = sqlx::query_as::<_, (i32,)>(
+        "SELECT current_streak FROM wdl_database.quote_scores WHERE guild_id = ? AND user_id = ? FOR UPDATE",
+    )
+        .bind(i64::from(guild_id))
+        .bind(user_id)
+        .fetch_optional(&mut **transaction)
+        .await?;
+    Ok(row.map(|(streak,)| streak).unwrap_or(0))
+}
+
+/// Books an entry and adds it to the player's totals. Returns their new
+/// all-time totals.
+pub async fn append(transaction: &mut Transaction<'_, MySql>, entry: &Entry<'_>) -> Result<Totals, sqlx::Error> {
+    let (attempts, correct) = match entry.outcome {
+        Some(is_correct) => (1, i32::from(is_correct)),
+        None => (0, 0),
+    };
+
+    sqlx::query(
+        "INSERT INTO wdl_database.quote_points_ledger
+         (guild_id, user_id, season_id, kind, points, attempts, correct, round_id, reason, created_by)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+        .bind(i64::from(entry.guild_id))
+        .bind(entry.user_id)
+        .bind(entry.season_id)
+        .bind(entry.kind.name())
+        .bind(entry.points)
+        .bind(attempts)
+        .bind(correct)
+        .bind(entry.round_id)
+        .bind(entry.reason)
+        .bind(entry.created_by)
+        .execute(&mut **transaction)
+        .await?;
+
+    let row = Row { points: entry.points, attempts, correct, ..Row::default() };
+    if let Some(season_id) = entry.season_id {
+        add_row(transaction, Scope::Season(season_id), entry.user_id, &row).await?;
+    }
+    add_row(transaction, Scope::Guild(entry.guild_id), entry.user_id, &row).await
+}
+
+/// Books a single entry in a transaction of its own.
+pub async fn record(db_pool: &MySqlPool, entry: &Entry<'_>) -> Result<Totals, sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let totals = append(&mut transaction, entry).await?;
+    transaction.commit().await?;
+    Ok(totals)
+}
+
+/// Cancels every entry of a round that isn't reversed yet with an opposite entry.
+/// Returns the round id and the points taken from or given back to each player.
+async fn revert_round(
+    db_pool: &MySqlPool,
+    guild_id: GuildId,
+    round_prefix: &str,
+    reason: Option<&str>,
+    reverted_by: i64,
+) -> Result<Result<(String, Vec<(i64, i32)>), &'static str>, sqlx::Error> {
+    // The prefix ends up in a LIKE pattern, where anything but hex and dashes could match more
+    if !valid_round_prefix(round_prefix) {
+        return Ok(Err("That isn't a round id, they only hold the digits 0-9, letters a-f and dashes."));
+    }
+    let mut transaction = db_pool.begin().await?;
+
+    let rounds = sqlx::query_as::<_, (String,)>(
+        "SELECT id FROM wdl_database.quote_rounds WHERE guild_id = ? AND id LIKE CONCAT(?, '%') LIMIT 2",
+    )
+        .bind(i64::from(guild_id))
+        .bind(round_prefix)
+        .fetch_all(&mut *transaction)
+        .await?;
+    let round_id = match rounds.as_slice() {
+        [(round_id,)] => round_id.clone(),
+        [] => return Ok(Err("No round in this server has that id.")),
+        _ => return Ok(Err("That id matches several rounds, use more of it.")),
+    };
+
+    let entries = sqlx::query_as::<_, (i64, i64, Option<i32>, i32, i32, i32)>(
+        "SELECT entry.id, entry.user_id, entry.season_id, entry.points, entry.attempts, entry.correct
+         FROM wdl_database.quote_points_ledger entry
+         LEFT JOIN wdl_database.quote_points_ledger reversal ON reversal.reverses_id = entry.id
+         WHERE entry.guild_id = ? AND entry.round_id = ? AND entry.reverses_id IS NULL AND reversal.id IS NULL
+         ORDER BY entry.id
+         FOR UPDATE",
+    )
+        .bind(i64::from(guild_id))
+        .bind(&round_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    let mut changes: Vec<(i64, i32)> = Vec::new();
+    let mut touched: Vec<(i64, Option<i32>)> = Vec::new();
+    for &(entry_id, user_id, season_id, points, attempts, correct) in entries.iter() {
+        sqlx::query(
+            "INSERT INTO wdl_database.quote_points_ledger
+             (guild_id, user_id, season_id, kind, points, attempts, correct, round_id, reverses_id, reason, created_by)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+            .bind(i64::from(guild_id))
+            .bind(user_id)
+            .bind(season_id)
+            .bind(Kind::Reversal.name())
+            .bind(-points)
+            .bind(-attempts)
+            .bind(-correct)
+            .bind(&round_id)
+            .bind(entry_id)
+            .bind(reason)
+            .bind(reverted_by)
+            .execute(&mut *transaction)
+            .await?;
+
+        match changes.iter_mut().find(|(id, _)| *id == user_id) {
+            Some((_, change)) => *change -= points,
+            None => changes.push((user_id, -points)),
+        }
+        if !touched.contains(&(user_id, season_id)) {
+            touched.push((user_id, season_id));
+        }
+    }
+
+    for &(user_id, season_id) in touched.iter() {
+        if let Some(season_id) = season_id {
+            refresh(&mut transaction, Scope::Season(season_id), user_id).await?;
+        }
+        refresh(&mut transaction, Scope::Guild(guild_id), user_id).await?;
+    }
+
+    // The round leaves the stats, and the quote's difficulty if the round counted towards it
+    let marked = sqlx::query(
+        "UPDATE wdl_database.quote_rounds SET reverted_at = CURRENT_TIMESTAMP WHERE id = ? AND reverted_at IS NULL",
+    )
+        .bind(&round_id)
+        .execute(&mut *transaction)
+        .await?;
+    if marked.rows_affected() > 0 {
+        let counted = sqlx::query_as::<_, (i64, Option<i64>)>(
+            "SELECT r.message_id, CAST(SUM(g.correct) AS SIGNED)
+             FROM wdl_database.quote_rounds r
+             JOIN wdl_database.quote_guesses g ON g.round_id = r.id
+             WHERE r.id = ? AND r.mode IN ('text', 'buttons') AND r.cancelled = FALSE AND r.ended_at IS NOT NULL
+             GROUP BY r.message_id",
+        )
+            .bind(&round_id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        if let Some((message_id, correct)) = counted {
+            difficulty::unrecord_round(&mut *transaction, message_id, correct.unwrap_or(0) > 0).await?;
+        }
+    }
+
+    transaction.commit().await?;
+    info!("Reverted {} ledger entries of round {} in guild {} for {}", entries.len(), round_id, guild_id, reverted_by);
+    Ok(Ok((round_id, changes)))
+}
+
+/// Rebuilds every all-time and season total of the guild from the ledger.
+/// Returns how many all-time and season totals were rewritten.
+async fn recompute(db_pool: &MySqlPool, guild_id: GuildId) -> Result<(usize, usize), sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+
+    let players = sqlx::query_as::<_, (i64,)>(
+        "SELECT DISTINCT user_id FROM wdl_database.quote_points_ledger WHERE guild_id = ?",
+    )
+        .bind(i64::from(guild_id))
+        .fetch_all(&mut *transaction)
+        .await?;
+    for &(user_id,) in players.iter() {
+        refresh(&mut transaction, Scope::Guild(guild_id), user_id).await?;
+    }
+
+    let season_players = sqlx::query_as::<_, (i32, i64)>(
+        "SELECT DISTINCT season_id, user_id FROM wdl_database.quote_points_ledger WHERE guild_id = ? AND season_id IS NOT NULL",
+    )
+        .bind(i64::from(guild_id))
+        .fetch_all(&mut *transaction)
+        .await?;
+    for &(season_id, user_id) in season_players.iter() {
+        refresh(&mut transaction, Scope::Season(season_id), user_id).await?;
+    }
+
+    transaction.commit().await?;
+    info!("Recomputed {} totals and {} season totals in guild {}", players.len(), season_players.len(), guild_id);
+    Ok((players.len(), season_players.len()))
+}
+
+/// The latest entries of a player, newest first.
+async fn history(db_pool: &MySqlPool, guild_id: GuildId, user_id: i64) -> Result<String, sqlx::Error> {
+    let entries = sqlx::query_as::<_, (i64, String, i32, Option<String>, Option<i64>, Option<String>, chrono::DateTime<chrono::Utc>)>(
+        "SELECT id, kind, points, round_id, reverses_id, reason, created_at
+         FROM wdl_database.quote_points_ledger
+         WHERE guild_id = ? AND user_id = ?
+         ORDER BY id DESC
+         LIMIT ?",
+    )
+        .bind(i64::from(guild_id))
+        .bind(user_id)
+        .bind(HISTORY_ENTRIES)
+        .fetch_all(db_pool)
+        .await?;
+
+    if entries.is_empty() {
+        return Ok(format!("<@{}> has no points on record.", user_id));
+    }
+
+    let mut lines = vec![format!("🧾 **Latest points of <@{}>:**", user_id)];
+    for (id, kind, points, round_id, reverses_id, reason, created_at) in entries {
+        let mut line = format!("`#{}` {} **{:+}** {}", id, created_at.format("%Y-%m-%d"), points, kind);
+        if let Some(round_id) = round_id {
+            line.push_str(&format!(" · round `{}`", &round_id[..MIN_ROUND_PREFIX.min(round_id.len())]));
+        }
+        if let Some(reverses_id) = reverses_id {
+            line.push_str(&format!(" · reverses `#{}`", reverses_id));
+        }
+        if let Some(reason) = reason {
+            line.push_str(&format!(" · {}", reason.chars().take(HISTORY_REASON_CHARS).collect::<String>()));
+        }
+        lines.push(line);
+    }
+    Ok(lines.join("\n"))
+}
+
+pub fn register() -> CreateCommand {
+    let adjust_option = CreateCommandOption::new(CommandOptionType::SubCommand, "adjust", "Give or take points from a player")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "The player to adjust").required(true))
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "points", "Points to add, negative to take away")
+                .required(true),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "reason", "Why the points change")
+                .required(true)
+                .max_length(255),
+        );
+    let revert_option = CreateCommandOption::new(CommandOptionType::SubCommand, "revert", "Undo every point booked for a round")
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "round", "The round id, or at least its first 8 characters")
+                .required(true)
+                .min_length(MIN_ROUND_PREFIX as u16)
+                .max_length(36),
+        )
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Why the round is reverted").max_length(255));
+    let recompute_option = CreateCommandOption::new(CommandOptionType::SubCommand, "recompute", "Rebuild every total from the points ledger");
+    let history_option = CreateCommandOption::new(CommandOptionType::SubCommand, "history", "Show the latest point changes of a player")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "The player to look up").required(true));
+
+    CreateCommand::new("quotepoints")
+        .description("Audit and correct guessquote points")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .add_option(adjust_option)
+        .add_option(revert_option)
+        .add_option(recompute_option)
+        .add_option(history_option)
+}
+
+fn string_option<'a>(options: &[ResolvedOption<'a>], name: &str) -> Option<&'a str> {
+    options.iter().find_map(|option| match option.value {
+        ResolvedValue::String(value) if option.name == name => Some(value.trim()),
+        _ => None,
+    })
+}
+
+fn user_option(options: &[ResolvedOption<'_>], name: &str) -> Option<i64> {
+    options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) if option.name == name => Some(i64::from(user.id)),
+        _ => None,
+    })
+}
+
+pub async fn handle_commands(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = command.guild_id else {
+        return Err("quotepoints command used outside of a guild".into());
+    };
+    let admin_id = i64::from(command.user.id);
+
+    let options = command.data.options();
+    let result = match options.first() {
+        Some(ResolvedOption { name: "adjust", value: ResolvedValue::SubCommand(options), .. }) => {
+            let points = options.iter().find_map(|option| match option.value {
+                ResolvedValue::Integer(points) if option.name == "points" => Some(points.clamp(-MAX_ADJUSTMENT, MAX_ADJUSTMENT) as i32),
+                _ => None,
+            });
+            match (user_option(options, "user"), points, string_option(options, "reason")) {
+                (Some(user_id), Some(points), Some(reason)) => {
+                    let season_id = seasons::current_season(db_pool, guild_id).await.map(|(season_id, _)| season_id);
+                    record(db_pool, &Entry::adjustment(guild_id, user_id, season_id, points, reason, admin_id))
+                        .await
+                        .map(|totals| {
+                            info!("Adjusted the points of {} in guild {} by {} for {}: {}", user_id, guild_id, points, admin_id, reason);
+                            format!("🧾 Adjusted <@{}> by **{:+}** points: {}. They now have {} points.", user_id, points, reason, totals.points)
+                        })
+                }
+                _ => Ok("A player, points and a reason are needed.".to_string()),
+            }
+        }
+        Some(ResolvedOption { name: "revert", value: ResolvedValue::SubCommand(options), .. }) => {
+            let round_prefix = string_option(options, "round").unwrap_or_default();
+            revert_round(db_pool, guild_id, round_prefix, string_option(options, "reason"), admin_id)
+                .await
+                .map(|reverted| match reverted {
+                    Ok((round_id, changes)) if changes.is_empty() => {
+                        format!("Round `{}` has no points left to revert.", round_id)
+                    }
+                    Ok((round_id, changes)) => {
+                        let changes: Vec<String> = changes.iter()
+                            .map(|(user_id, change)| format!("<@{}> {:+}", user_id, change))
+                            .collect();
+                        format!(
+                            "↩️ Reverted round `{}`: {}\nSkill ratings from the round aren't undone.",
+                            round_id,
+                            changes.join(", "),
+                        )
+                    }
+                    Err(message) => message.to_string(),
+                })
+        }
+        Some(ResolvedOption { name: "recompute", .. }) => recompute(db_pool, guild_id).await.map(|(players, seasons)| {
+            format!("🔁 Rebuilt the totals of {} players and {} season standings from the points ledger.", players, seasons)
+        }),
+        Some(ResolvedOption { name: "history", value: ResolvedValue::SubCommand(options), .. }) => {
+            match user_option(options, "user") {
+                Some(user_id) => history(db_pool, guild_id, user_id).await,
+                None => Ok("Pick a player to look up.".to_string()),
+            }
+        }
+        _ => Ok("Unknown subcommand.".to_string()),
+    };
+
+    let response = match result {
+        Ok(content) => CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+        Err(e) => {
+            warn!("Failed to handle quotepoints command: {}", e);
+            CreateInteractionResponseMessage::new()
+                .content("Sorry, the points ledger couldn't be updated right now.")
+                .ephemeral(true)
+        }
+    };
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guess(id: i64, points: i32, correct: bool) -> Row {
+        Row { id, points, attempts: 1, correct: i32::from(correct), ..Row::default() }
+    }
+
+    fn reversal(id: i64, of: &Row) -> Row {
+        Row {
+            id,
+            points: -of.points,
+            attempts: -of.attempts,
+            correct: -of.correct,
+            reverses_id: Some(of.id),
+            ..Row::default()
+        }
+    }
+
+    #[test]
+    fn totals_follow_the_entries() {
+        let rows = [guess(1, 80, true), guess(2, 90, true), guess(3, -5, false), guess(4, 70, true)];
+        assert_eq!(
+            fold(&rows),
+            Totals { points: 235, attempts: 4, correct: 3, current_streak: 1, best_streak: 2 }
+        );
+        assert_eq!(fold(&[]), Totals::default());
+    }
+
+    #[test]
+    fn opening_balances_carry_streaks_over() {
+        let opening = Row { id: 1, points: 500, attempts: 20, correct: 12, streak: Some(3), best_streak: Some(6), ..Row::default() };
+        let totals = fold(&[opening, guess(2, 60, true)]);
+        assert_eq!(totals, Totals { points: 560, attempts: 21, correct: 13, current_streak: 4, best_streak: 6 });
+    }
+
+    #[test]
+    fn reversals_cancel_points_and_streaks() {
+        let wrong = guess(2, -5, false);
+        let adjustment = Row { id: 4, points: 25, ..Row::default() };
+        let rows = [guess(1, 80, true), wrong, guess(3, 75, true), adjustment, reversal(5, &wrong)];
+        assert_eq!(
+            fold(&rows),
+            Totals { points: 180, attempts: 2, correct: 2, current_streak: 2, best_streak: 2 }
+        );
+    }
+
+    #[test]
+    fn applying_entries_matches_the_fold() {
+        let opening = Row { id: 1, points: 500, attempts: 20, correct: 12, streak: Some(3), best_streak: Some(6), ..Row::default() };
+        let rows = [opening, guess(2, 60, true), guess(3, -5, false), Row { id: 4, points: 25, ..Row::default() }, guess(5, 70, true)];
+        let mut totals = Totals::default();
+        for row in rows.iter() {
+            apply(&mut totals, row, false);
+        }
+        assert_eq!(totals, fold(&rows));
+    }
+
+    #[test]
+    fn round_prefixes_are_hex_and_dashes() {
+        assert!(valid_round_prefix("3f2a9c1e"));
+        assert!(valid_round_prefix("3f2a9c1e-77b0-4c1d-9e6a-0b1c2d3e4f50"));
+        assert!(!valid_round_prefix("3f2a9c1"));
+        assert!(!valid_round_prefix("3f2a9c1e%"));
+        assert!(!valid_round_prefix("3f2a_c1e"));
+    }
+}
@@ -0,0 +1,113 @@
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::SeedableRng;
+use std::collections::{HashMap, HashSet};
+
+/// How many words of context pick the next word.
+const ORDER: usize = 2;
+/// Generated messages stop after this many words.
+const MAX_WORDS: usize = 40;
+/// Generated messages shorter than this are thrown away.
+const MIN_WORDS: usize = 6;
+/// How many messages are generated before giving up on an original one.
+const ATTEMPTS: usize = 50;
+
+/// A word-level Markov chain trained on someone's messages, used to make up
+/// messages that sound like them.
+#[derive(Debug, Default)]
+pub struct MarkovChain {
+    /// The opening words of every training message.
+    starts: Vec<Vec<String>>,
+    /// The words seen after each run of `ORDER` words, `None` ending the message.
+    transitions: HashMap<Vec<String>, Vec<Option<String>>>,
+    /// The training messages, so they aren't handed back as fakes.
+    originals: HashSet<String>,
+}
+
+impl MarkovChain {
+    /// Trains a chain on messages. Messages too short to give any context are skipped.
+    pub fn train<'a>(messages: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut chain = MarkovChain::default();
+        for message in messages {
+            let words: Vec<String> = message.split_whitespace().map(str::to_string).collect();
+            if words.len() <= ORDER {
+                continue;
+            }
+
+            chain.starts.push(words[..ORDER].to_vec());
+            for start in 0..=words.len() - ORDER {
+                let next = words.get(start + ORDER).cloned();
+                chain.transitions.entry(words[start..start + ORDER].to_vec()).or_default().push(next);
+            }
+            chain.originals.insert(words.join(" "));
+        }
+        chain
+    }
+
+    /// Makes up a message that isn't one of the training messages. The same seed
+    /// gives the same message, so a round can be reproduced from its logged seed.
+    pub fn generate(&self, seed: u64) -> Option<String> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..ATTEMPTS {
+            let mut words = self.starts.choose(&mut rng)?.clone();
+            while words.len() < MAX_WORDS {
+                let next = self.transitions.get(&words[words.len() - ORDER..]).and_then(|next| next.choose(&mut rng));
+                match next {
+                    Some(Some(word)) => words.push(word.clone()),
+                    _ => break,
+                }
+            }
+
+            let message = words.join(" ");
+            if words.len() >= MIN_WORDS && !self.originals.contains(&message) {
+                return Some(message);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGES: [&str; 4] = [
+        "I think the new patch is really good for tanks",
+        "the new patch is really bad for healers honestly",
+        "I think the healers are fine in ranked right now",
+        "ranked right now is a mess with the new patch",
+    ];
+
+    #[test]
+    fn same_seed_gives_same_message() {
+        let chain = MarkovChain::train(MESSAGES);
+        assert_eq!(chain.generate(42), chain.generate(42));
+        assert!(chain.generate(42).is_some());
+    }
+
+    #[test]
+    fn never_repeats_a_training_message() {
+        let chain = MarkovChain::train(MESSAGES);
+        for seed in 0..100 {
+            if let Some(message) = chain.generate(seed) {
+                assert!(!MESSAGES.contains(&message.as_str()), "seed {} repeated {:?}", seed, message);
+                assert!(message.split_whitespace().count() >= MIN_WORDS);
+            }
+        }
+    }
+
+    #[test]
+    fn only_uses_words_from_training() {
+        let chain = MarkovChain::train(MESSAGES);
+        let vocabulary: HashSet<&str> = MESSAGES.iter().flat_map(|message| message.split_whitespace()).collect();
+        let message = chain.generate(7).unwrap();
+        assert!(message.split_whitespace().all(|word| vocabulary.contains(word)));
+    }
+
+    #[test]
+    fn needs_messages_that_branch() {
+        assert_eq!(MarkovChain::train([]).generate(1), None);
+        assert_eq!(MarkovChain::train(["one two"]).generate(1), None);
+        assert_eq!(MarkovChain::train(["a message that never branches anywhere at all"]).generate(1), None);
+    }
+}
@@ -0,0 +1,210 @@
+use log::{info, warn};
+use serenity::all::{ChannelId, CommandOptionType, CreateCommandOption, GuildId, ResolvedOption, ResolvedValue, UserId};
+use sqlx::MySqlPool;
+use std::cmp::Reverse;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Most quotes a single match may have.
+const MAX_ROUNDS: i64 = 10;
+/// Pause between the quotes of a match, while the standings are up.
+pub const INTERLUDE: Duration = Duration::from_secs(8);
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::Integer, "rounds", "Play several quotes in a row as a match")
+        .min_int_value(1)
+        .max_int_value(MAX_ROUNDS as u64)
+}
+
+/// Reads the `rounds` option, a single quote is the default.
+pub fn rounds_from_options(options: &[ResolvedOption]) -> usize {
+    options.iter()
+        .find_map(|option| match option.value {
+            ResolvedValue::Integer(rounds) if option.name == "rounds" => Some(rounds.clamp(1, MAX_ROUNDS) as usize),
+            _ => None,
+        })
+        .unwrap_or(1)
+}
+
+/// Running point totals of a match.
+#[derive(Debug, Default)]
+pub struct Standings {
+    points: Vec<(UserId, i32)>,
+}
+
+impl Standings {
+    pub fn add(&mut self, user_id: UserId, points: i32) {
+        match self.points.iter_mut().find(|(id, _)| *id == user_id) {
+            Some((_, total)) => *total += points,
+            None => self.points.push((user_id, points)),
+        }
+    }
+
+    /// Players with their placement and points, best first. Tied players share a
+    /// placement and keep the order they first scored in.
+    pub fn ranked(&self) -> Vec<(usize, UserId, i32)> {
+        let mut sorted = self.points.clone();
+        sorted.sort_by_key(|&(_, points)| Reverse(points));
+
+        let mut ranked: Vec<(usize, UserId, i32)> = Vec::with_capacity(sorted.len());
+        for (index, (user_id, points)) in sorted.into_iter().enumerate() {
+            let placement = match ranked.last() {
+                Some(&(previous_placement, _, previous_points)) if previous_points == points => previous_placement,
+                _ => index + 1,
+            };
+            ranked.push((placement, user_id, points));
+        }
+        ranked
+    }
+
+    /// One line per player, with medals for the podium at the end of a match.
+    pub fn render(&self, podium: bool) -> String {
+        if self.points.is_empty() {
+            return "Nobody has scored yet.".to_string();
+        }
+
+        self.ranked()
+            .into_iter()
+            .map(|(placement, user_id, points)| {
+                let marker = match placement {
+                    1 if podium => "🥇".to_string(),
+                    2 if podium => "🥈".to_string(),
+                    3 if podium => "🥉".to_string(),
+                    _ => format!("{}.", placement),
+                };
+                format!("{} <@{}> - {} points", marker, user_id, points)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Identifies a match in `quote_matches` and ties its rounds together.
+pub struct MatchRecord {
+    pub id: String,
+}
+
+/// Where a match is played and how long it runs.
+pub struct NewMatch<'a> {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub started_by: UserId,
+    pub mode: &'a str,
+    pub rounds: usize,
+}
+
+/// Stores the match when it starts. Like round history, failing to write it
+/// never stops the game.
+pub async fn record_match_start(db_pool: &MySqlPool, new_match: NewMatch<'_>) -> MatchRecord {
+    let record = MatchRecord { id: Uuid::new_v4().to_string() };
+
+    if let Err(e) = sqlx::query(
+        "INSERT INTO wdl_database.quote_matches (id, guild_id, channel_id, started_by, mode, rounds)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+        .bind(&record.id)
+        .bind(i64::from(new_match.guild_id))
+        .bind(i64::from(new_match.channel_id))
+        .bind(i64::from(new_match.started_by))
+        .bind(new_match.mode)
+        .bind(new_match.rounds as i32)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record match {}: {}", record.id, e);
+    } else {
+        info!("Recorded start of match {}", record.id);
+    }
+
+    record
+}
+
+/// Stores the final standings of a match, also when it was cancelled part way.
+pub async fn record_match_end(
+    db_pool: &MySqlPool,
+    record: &MatchRecord,
+    rounds_played: usize,
+    cancelled: bool,
+    standings: &Standings,
+) {
+    let ranked = standings.ranked();
+    let winner = ranked.first().filter(|_| !cancelled).map(|&(_, user_id, _)| i64::from(user_id));
+
+    if let Err(e) = sqlx::query(
+        "UPDATE wdl_database.quote_matches
+         SET ended_at = CURRENT_TIMESTAMP, rounds_played = ?, cancelled = ?, winner_id = ?
+         WHERE id = ?",
+    )
+        .bind(rounds_played as i32)
+        .bind(cancelled)
+        .bind(winner)
+        .bind(&record.id)
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to record end of match {}: {}", record.id, e);
+    }
+
+    for (placement, user_id, points) in ranked {
+        if let Err(e) = sqlx::query(
+            "INSERT INTO wdl_database.quote_match_results (match_id, user_id, points, placement)
+             VALUES (?, ?, ?, ?)",
+        )
+            .bind(&record.id)
+            .bind(i64::from(user_id))
+            .bind(points)
+            .bind(placement as i32)
+            .execute(db_pool)
+            .await
+        {
+            warn!("Failed to record result of {} in match {}: {}", user_id, record.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: u64) -> UserId {
+        UserId::new(id)
+    }
+
+    #[test]
+    fn standings_add_up_rounds() {
+        let mut standings = Standings::default();
+        standings.add(user(1), 80);
+        standings.add(user(2), 95);
+        standings.add(user(1), 40);
+        standings.add(user(3), -5);
+        assert_eq!(standings.ranked(), vec![(1, user(1), 120), (2, user(2), 95), (3, user(3), -5)]);
+    }
+
+    #[test]
+    fn ties_share_a_placement() {
+        let mut standings = Standings::default();
+        standings.add(user(1), 50);
+        standings.add(user(2), 70);
+        standings.add(user(3), 50);
+        standings.add(user(4), 10);
+        assert_eq!(
+            standings.ranked(),
+            vec![(1, user(2), 70), (2, user(1), 50), (2, user(3), 50), (4, user(4), 10)]
+        );
+    }
+
+    #[test]
+    fn podium_gets_medals() {
+        let mut standings = Standings::default();
+        standings.add(user(1), 100);
+        standings.add(user(2), 60);
+        standings.add(user(3), 30);
+        standings.add(user(4), 5);
+        assert_eq!(
+            standings.render(true),
+            "🥇 <@1> - 100 points\n🥈 <@2> - 60 points\n🥉 <@3> - 30 points\n4. <@4> - 5 points"
+        );
+        assert!(standings.render(false).starts_with("1. <@1> - 100 points"));
+        assert_eq!(Standings::default().render(true), "Nobody has scored yet.");
+    }
+}
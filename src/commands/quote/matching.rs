@@ -0,0 +1,147 @@
+use std::collections::BTreeSet;
+
+/// A user that a free-text guess can refer to, with every name they go by:
+/// usernames from the archive, guild nicknames and admin-managed aliases.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub user_id: i64,
+    pub names: Vec<String>,
+}
+
+/// Who a free-text guess points at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuessMatch {
+    /// The guess doesn't name any candidate.
+    NoMatch,
+    /// The guess names exactly one candidate.
+    Single(i64),
+    /// The guess names several candidates and is rejected.
+    Ambiguous(Vec<i64>),
+}
+
+/// Resolves a guess to a candidate. Mentions count directly; names are matched
+/// on whole words, allowing a small number of typos for longer names.
+pub fn match_guess(content: &str, mentioned_ids: &[i64], candidates: &[Candidate]) -> GuessMatch {
+    let words = normalize(content);
+    let mut matched = BTreeSet::new();
+
+    for candidate in candidates {
+        let mentioned = mentioned_ids.contains(&candidate.user_id);
+        if mentioned || candidate.names.iter().any(|name| names_in(&words, name)) {
+            matched.insert(candidate.user_id);
+        }
+    }
+
+    match matched.len() {
+        0 => GuessMatch::NoMatch,
+        1 => GuessMatch::Single(*matched.iter().next().unwrap_or(&0)),
+        _ => GuessMatch::Ambiguous(matched.into_iter().collect()),
+    }
+}
+
+/// Lowercases the text and splits it into alphanumeric words.
+pub fn normalize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Number of typos tolerated for a name of the given length.
+pub fn allowed_distance(name_length: usize) -> usize {
+    match name_length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Checks whether the name appears as a run of whole words in the guess.
+fn names_in(words: &[String], name: &str) -> bool {
+    let name_words = normalize(name);
+    if name_words.is_empty() || name_words.len() > words.len() {
+        return false;
+    }
+
+    let name = name_words.join(" ");
+    let max_distance = allowed_distance(name.chars().count());
+
+    words.windows(name_words.len()).any(|window| {
+        let phrase = window.join(" ");
+        phrase == name || (max_distance > 0 && edit_distance(&phrase, &name) <= max_distance)
+    })
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<Candidate> {
+        vec![
+            Candidate { user_id: 1, names: vec!["Flixis".into(), "flix".into()] },
+            Candidate { user_id: 2, names: vec!["Al".into()] },
+            Candidate { user_id: 3, names: vec!["Alexander".into(), "Big Lasagna".into()] },
+        ]
+    }
+
+    #[test]
+    fn edit_distance_counts_changes() {
+        assert_eq!(edit_distance("flixis", "flixis"), 0);
+        assert_eq!(edit_distance("flixis", "flixsi"), 2);
+        assert_eq!(edit_distance("flixis", "flxis"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn matches_name_alias_and_mention() {
+        let candidates = candidates();
+        assert_eq!(match_guess("pretty sure it's Flixis", &[], &candidates), GuessMatch::Single(1));
+        assert_eq!(match_guess("flix!", &[], &candidates), GuessMatch::Single(1));
+        assert_eq!(match_guess("that's the big lasagna", &[], &candidates), GuessMatch::Single(3));
+        assert_eq!(match_guess("<@2>", &[2], &candidates), GuessMatch::Single(2));
+    }
+
+    #[test]
+    fn tolerates_typos_in_longer_names() {
+        let candidates = candidates();
+        assert_eq!(match_guess("flixsi", &[], &candidates), GuessMatch::NoMatch);
+        assert_eq!(match_guess("flxis", &[], &candidates), GuessMatch::Single(1));
+        assert_eq!(match_guess("alexnader", &[], &candidates), GuessMatch::Single(3));
+    }
+
+    #[test]
+    fn short_names_need_whole_word_match() {
+        let candidates = candidates();
+        // "al" is inside "alexander" but must not match on its own
+        assert_eq!(match_guess("alexander", &[], &candidates), GuessMatch::Single(3));
+        assert_eq!(match_guess("also no idea", &[], &candidates), GuessMatch::NoMatch);
+        assert_eq!(match_guess("al", &[], &candidates), GuessMatch::Single(2));
+    }
+
+    #[test]
+    fn naming_several_candidates_is_ambiguous() {
+        let candidates = candidates();
+        assert_eq!(match_guess("flixis or al", &[], &candidates), GuessMatch::Ambiguous(vec![1, 2]));
+        assert_eq!(match_guess("<@1> <@3>", &[1, 3], &candidates), GuessMatch::Ambiguous(vec![1, 3]));
+    }
+}
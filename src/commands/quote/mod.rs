@@ -0,0 +1,1829 @@
+use chrono::Utc;
+use log::{info, warn, error};
+use rand::{seq::SliceRandom, Rng};
+use serenity::all::{
+    ButtonStyle, ChannelId, CommandInteraction, CommandOptionType, ComponentInteractionCollector,
+    CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse, EditMessage, GuildId, Message,
+    MessageId, ResolvedOption, ResolvedValue, UserId,
+};
+use serenity::futures::{future::join_all, StreamExt};
+use sqlx::MySqlPool;
+use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use crate::{ALLOWED_QUOTE_USERS, GUESSQUOTE_RULES};
+
+mod aliases;
+mod blanks;
+mod daily;
+mod difficulty;
+mod duel;
+mod fairplay;
+mod games;
+mod hints;
+mod history;
+mod ledger;
+mod markov;
+mod matches;
+mod matching;
+mod rating;
+mod render;
+mod reverse;
+mod rules;
+mod scoring;
+mod seasons;
+mod selection;
+mod stats;
+mod threads;
+mod wagers;
+mod when;
+
+pub use aliases::{handle_commands as handle_aliases, register as register_aliases};
+pub use daily::{
+    handle_commands as handle_daily, handle_component as handle_daily_component, register as register_daily,
+    spawn_reveals as spawn_daily_reveals, DAILY_BUTTON_PREFIX,
+};
+pub use duel::{handle_commands as handle_duel, register as register_duel};
+pub use games::GameRegistry;
+pub use ledger::{handle_commands as handle_ledger, register as register_ledger};
+pub use rules::RoundRules;
+pub use seasons::{handle_commands as handle_seasons, register as register_seasons};
+pub use selection::{QuotePool, SelectionSettings};
+pub use stats::{handle_commands as handle_stats, register as register_stats};
+use difficulty::Difficulty;
+use games::{GameMode, RoundState};
+use markov::MarkovChain;
+use matches::Standings;
+use matching::{Candidate, GuessMatch};
+use selection::QuoteFilter;
+use when::WhenGuess;
+
+const GUESS_BUTTON_PREFIX: &str = "guessquote:";
+/// Button ids of the "real or fake" votes, passed through the button collector like candidates.
+const REAL_VERDICT: i64 = 1;
+const FAKE_VERDICT: i64 = 0;
+/// How many of the author's latest messages an impostor quote is trained on.
+const MARKOV_TRAINING_MESSAGES: i64 = 5000;
+/// How many quotes a round tries before giving up on finding one its mode can use.
+const PICK_ATTEMPTS: usize = 5;
+
+/// The users whose messages can be quoted in this guild.
+fn allowed_users(guild_id: GuildId) -> &'static [i64] {
+    ALLOWED_QUOTE_USERS.get().map(|users| users.for_guild(guild_id)).unwrap_or(&[])
+}
+
+/// Assigns rows stored before the game was scoped per guild to the given guild.
+pub async fn assign_unscoped_rows(db_pool: &MySqlPool, guild_id: GuildId) {
+    let updates = [
+        "UPDATE wdl_database.discord_messages SET GuildId = ? WHERE GuildId = 0",
+        "UPDATE wdl_database.quote_scores SET guild_id = ? WHERE guild_id = 0",
+        "UPDATE wdl_database.quote_seasons SET guild_id = ? WHERE guild_id = 0",
+        "UPDATE wdl_database.quote_rounds SET guild_id = ? WHERE guild_id = 0",
+        "UPDATE wdl_database.user_aliases SET guild_id = ? WHERE guild_id = 0",
+        "UPDATE wdl_database.quote_points_ledger SET guild_id = ? WHERE guild_id = 0",
+    ];
+
+    for update in updates {
+        match sqlx::query(update).bind(i64::from(guild_id)).execute(db_pool).await {
+            Ok(result) if result.rows_affected() > 0 => {
+                info!("Assigned {} unscoped rows to guild {}: {}", result.rows_affected(), guild_id, update);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to assign unscoped rows to guild {}: {}", guild_id, e),
+        }
+    }
+}
+
+/// A single answer collected during a round.
+struct Guess {
+    user_id: UserId,
+    /// The candidate the player picked, if the guess named exactly one.
+    guessed_user_id: Option<i64>,
+    is_correct: bool,
+    /// Time between the quote being posted and this guess arriving.
+    elapsed: Duration,
+    /// What the guess answered in modes that don't name the author.
+    detail: Option<GuessDetail>,
+}
+
+/// What a single round of a game is played with.
+#[derive(Clone, Copy)]
+struct RoundSetup<'a> {
+    guild_id: GuildId,
+    mode: GameMode,
+    rules: &'a RoundRules,
+    difficulty: Option<Difficulty>,
+    /// Which quote of the game this is, counting from 1.
+    number: usize,
+    /// How many quotes the game has.
+    rounds: usize,
+    match_id: Option<&'a str>,
+    /// Whether the quote is played in a thread of its own.
+    thread: bool,
+}
+
+/// Where a round's quote was posted. The first quote of a game answers the
+/// command, later quotes of a match and quotes in round threads are messages.
+enum QuotePost<'a> {
+    Reply(&'a CommandInteraction),
+    Posted(Box<Message>),
+}
+
+impl<'a> QuotePost<'a> {
+    async fn send(
+        ctx: &serenity::client::Context,
+        command: &'a CommandInteraction,
+        channel_id: ChannelId,
+        as_reply: bool,
+        content: String,
+        components: Vec<CreateActionRow>,
+    ) -> Result<QuotePost<'a>, serenity::Error> {
+        if as_reply {
+            let mut response = CreateInteractionResponseMessage::new().content(content);
+            if !components.is_empty() {
+                response = response.components(components);
+            }
+            command.create_response(&ctx.http, CreateInteractionResponse::Message(response)).await?;
+            Ok(QuotePost::Reply(command))
+        } else {
+            let mut message = CreateMessage::new().content(content);
+            if !components.is_empty() {
+                message = message.components(components);
+            }
+            Ok(QuotePost::Posted(Box::new(channel_id.send_message(&ctx.http, message).await?)))
+        }
+    }
+
+    async fn message_id(&self, ctx: &serenity::client::Context) -> Result<MessageId, serenity::Error> {
+        match self {
+            QuotePost::Reply(command) => Ok(command.get_response(&ctx.http).await?.id),
+            QuotePost::Posted(message) => Ok(message.id),
+        }
+    }
+
+    async fn set_components(&self, ctx: &serenity::client::Context, components: Vec<CreateActionRow>) -> Result<(), serenity::Error> {
+        match self {
+            QuotePost::Reply(command) => {
+                command.edit_response(&ctx.http, EditInteractionResponse::new().components(components)).await?;
+            }
+            QuotePost::Posted(message) => {
+                message.channel_id
+                    .edit_message(&ctx.http, message.id, EditMessage::new().components(components))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The answer of a guess in modes that don't name the author.
+enum GuessDetail {
+    /// The year or month guessed in a "when" round.
+    When(WhenGuess),
+    /// How many missing words a "finish the quote" reply filled.
+    Blanks { filled: usize, total: usize },
+    /// Whether a "real or fake" vote called the quote real.
+    Verdict { voted_real: bool },
+    /// The label of the quote picked in a reverse round.
+    Picked(&'static str),
+}
+
+impl GuessDetail {
+    fn describe(&self) -> String {
+        match self {
+            GuessDetail::When(when_guess) => when_guess.describe(),
+            GuessDetail::Blanks { filled, total } => format!("{}/{} words", filled, total),
+            GuessDetail::Verdict { voted_real: true } => "voted real".to_string(),
+            GuessDetail::Verdict { voted_real: false } => "voted fake".to_string(),
+            GuessDetail::Picked(label) => format!("picked {}", label),
+        }
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("scoreboard")
+        .description("View the guessquote game scoreboard")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::Integer, "season", "Show the standings of this server's season with this number instead of all-time")
+                .min_int_value(1),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "sort", "What to rank players by")
+                .add_string_choice("Points", "points")
+                .add_string_choice("Skill rating", "rating"),
+        )
+}
+
+pub fn register_guess() -> CreateCommand {
+    let start_option = rules::options().into_iter().fold(
+        CreateCommandOption::new(CommandOptionType::SubCommand, "start", "Start a round in this channel")
+            .add_sub_option(GameMode::option())
+            .add_sub_option(matches::option())
+            .add_sub_option(difficulty::option())
+            .add_sub_option(threads::option()),
+        |start_option, rule_option| start_option.add_sub_option(rule_option),
+    );
+    let status_option = CreateCommandOption::new(CommandOptionType::SubCommand, "status", "Show the round running in this channel");
+    let cancel_option = CreateCommandOption::new(CommandOptionType::SubCommand, "cancel", "Cancel the round running in this channel");
+
+    CreateCommand::new("guessquote")
+        .description("Start a game where you have to guess who said a quote")
+        .dm_permission(false)
+        .add_option(start_option)
+        .add_option(status_option)
+        .add_option(cancel_option)
+}
+
+// Command handler for the guessquote command and its subcommands
+pub async fn handle_guessquote(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+    games: &GameRegistry,
+    quotes: &QuotePool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match command.data.options.first().map(|option| option.name.as_str()) {
+        Some("status") => show_round_status(ctx, command, games).await,
+        Some("cancel") => cancel_round(ctx, command, games).await,
+        _ => guess_quote(ctx, command, db_pool, games, quotes).await,
+    }
+}
+
+/// Returns the options passed to the invoked subcommand.
+fn subcommand_options(command: &CommandInteraction) -> Vec<ResolvedOption<'_>> {
+    match command.data.options().into_iter().next() {
+        Some(ResolvedOption { value: ResolvedValue::SubCommand(options), .. }) => options,
+        _ => Vec::new(),
+    }
+}
+
+async fn reply_ephemeral(
+    ctx: &serenity::client::Context,
+    command: &CommandInteraction,
+    content: impl Into<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    command
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new().content(content).ephemeral(true),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// The game a status or cancel command is about. Commands used inside a round
+/// thread reach the game of the channel the thread belongs to.
+fn running_game(games: &GameRegistry, command: &CommandInteraction) -> Option<(ChannelId, Arc<RoundState>)> {
+    let parent_id = command.channel.as_ref().and_then(|channel| channel.parent_id);
+    std::iter::once(command.channel_id)
+        .chain(parent_id)
+        .find_map(|channel_id| games.get(channel_id).map(|round| (channel_id, round)))
+}
+
+async fn show_round_status(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    games: &GameRegistry,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let status = match running_game(games, command).map(|(_, round)| round) {
+        Some(round) if round.rounds > 1 => format!(
+            "A **{}** match started by <@{}> is on round {} of {}: {} seconds left, {} guesses so far.",
+            round.mode,
+            round.started_by,
+            round.round_number(),
+            round.rounds,
+            round.remaining().as_secs(),
+            round.guess_count()
+        ),
+        Some(round) => format!(
+            "A **{}** round started by <@{}> is running: {} seconds left, {} guesses so far.",
+            round.mode,
+            round.started_by,
+            round.remaining().as_secs(),
+            round.guess_count()
+        ),
+        None => "No guessquote round is running in this channel. Start one with `/guessquote start`.".to_string(),
+    };
+
+    reply_ephemeral(&ctx, command, status).await
+}
+
+async fn cancel_round(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    games: &GameRegistry,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some((channel_id, round)) = running_game(games, command) else {
+        return reply_ephemeral(&ctx, command, "There is no guessquote round to cancel in this channel.").await;
+    };
+
+    // Only the player who started the round or a moderator may stop it
+    let can_manage = command.member.as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_messages());
+    if round.started_by != command.user.id && !can_manage {
+        return reply_ephemeral(&ctx, command, "Only the player who started this round or a moderator can cancel it.").await;
+    }
+
+    games.cancel(channel_id, command.user.id);
+    info!("Guessquote round in channel {} cancelled by {}", channel_id, command.user.id);
+    reply_ephemeral(&ctx, command, "Cancelling the round...").await
+}
+
+pub async fn show_scoreboard(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = command.guild_id else {
+        return reply_ephemeral(&ctx, command, "The scoreboard is only available in servers.").await;
+    };
+    let options = command.data.options();
+    let season = options.iter().find_map(|option| match option.value {
+        ResolvedValue::Integer(season) if option.name == "season" => Some(season),
+        _ => None,
+    });
+    let by_rating = options.iter().any(|option| option.name == "sort" && matches!(option.value, ResolvedValue::String("rating")));
+    if by_rating && season.is_some() {
+        return reply_ephemeral(&ctx, command, "Skill ratings are all-time only, leave out the season to rank by rating.").await;
+    }
+
+    // Seasons are asked for by their number in the guild, the queries need the season's id
+    let season = match season {
+        Some(number) => match seasons::find_season(db_pool, guild_id, number).await {
+            Ok(Some(season)) => Some(season),
+            Ok(None) => return reply_ephemeral(&ctx, command, format!("This server has no season {} yet.", number)).await,
+            Err(e) => {
+                warn!("Failed to look up season {}: {}", number, e);
+                reply_ephemeral(&ctx, command, "Sorry, I couldn't fetch the scoreboard right now.").await?;
+                return Err(Box::new(e));
+            }
+        },
+        None => None,
+    };
+
+    info!("Fetching scoreboard (season: {:?}, by rating: {})...", season, by_rating);
+    // Every scoreboard lists the same columns, only where they come from and the order differ
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "WITH latest_names AS (
+            SELECT UserId,
+                   Name,
+                   ROW_NUMBER() OVER (PARTITION BY UserId ORDER BY Timestamp DESC) as rn
+            FROM wdl_database.discord_messages
+        )
+        SELECT qs.user_id,
+               COALESCE(ln.Name, CONVERT(qs.user_id, CHAR CHARACTER SET utf8mb4)) as Name,
+               qs.correct_guesses,
+               qs.total_attempts,
+               qs.points,
+               qs.current_streak,
+               qs.best_streak,
+               CAST((qs.correct_guesses * 100.0 / qs.total_attempts) AS DOUBLE) as accuracy, ",
+    );
+    match season {
+        // The season was looked up in this guild, so its id is enough
+        Some((season_id, _)) => {
+            query_builder.push("CAST(NULL AS DOUBLE) as rating FROM wdl_database.quote_season_scores qs");
+            query_builder.push(" LEFT JOIN latest_names ln ON ln.UserId = qs.user_id AND ln.rn = 1 WHERE qs.season_id = ");
+            query_builder.push_bind(season_id);
+        }
+        None => {
+            query_builder.push("qs.rating FROM wdl_database.quote_scores qs");
+            query_builder.push(" LEFT JOIN latest_names ln ON ln.UserId = qs.user_id AND ln.rn = 1 WHERE qs.guild_id = ");
+            query_builder.push_bind(i64::from(guild_id));
+        }
+    }
+    if by_rating {
+        query_builder.push(" AND qs.rated_rounds > 0 ORDER BY qs.rating DESC, qs.points DESC");
+    } else {
+        query_builder.push(" ORDER BY qs.points DESC, accuracy DESC");
+    }
+    query_builder.push(" LIMIT 10");
+
+    let result = query_builder
+        .build_query_as::<(i64, String, i32, i32, i32, i32, i32, Option<f64>, Option<f64>)>()
+        .fetch_all(db_pool)
+        .await;
+
+    match result {
+        Ok(scores) => {
+            info!("Found {} players on scoreboard", scores.len());
+            let mut scoreboard = match &season {
+                Some((_, season_name)) => format!("🏆 **GuessQuote Leaderboard - {}** 🏆\n\n", season_name),
+                None if by_rating => String::from("🏆 **GuessQuote Leaderboard (skill rating)** 🏆\n\n"),
+                None => String::from("🏆 **GuessQuote Leaderboard (all-time)** 🏆\n\n"),
+            };
+            for (index, (user_id, name, correct, total, points, current_streak, best_streak, accuracy, rating)) in scores.iter().enumerate() {
+                let accuracy_value = accuracy.unwrap_or(0.0);
+                info!("Rank {}: {} (ID: {}) - {} points, {}/{} correct, streak: {}/{} ({}% accuracy), rating: {:?}",
+                    index + 1, name, user_id, points, correct, total, current_streak, best_streak, accuracy_value.round(), rating);
+                let rating_text = match rating {
+                    Some(rating) => format!(" | Rating: {}", rating.round()),
+                    None => String::new(),
+                };
+                scoreboard.push_str(&format!(
+                    "{}. {} - {} points, {} correct out of {} attempts ({}% accuracy) | Streak: {} 🔥 (Best: {}){}\n",
+                    index + 1,
+                    name,
+                    points,
+                    correct,
+                    total,
+                    accuracy_value.round(),
+                    current_streak,
+                    best_streak,
+                    rating_text
+                ));
+            }
+
+            if scores.is_empty() {
+                scoreboard.push_str("No scores recorded yet! Start playing with /guessquote");
+            }
+
+            if season.is_none() {
+                if let Some((season_id, season_name)) = seasons::current_season(db_pool, guild_id).await {
+                    match seasons::season_number(db_pool, guild_id, season_id).await {
+                        Ok(number) => scoreboard.push_str(&format!(
+                            "\nNow playing: **{}**. See its standings with `/scoreboard season:{}`",
+                            season_name, number
+                        )),
+                        Err(e) => {
+                            warn!("Failed to number season {}: {}", season_id, e);
+                            scoreboard.push_str(&format!("\nNow playing: **{}**.", season_name));
+                        }
+                    }
+                }
+            }
+
+            if let Err(e) = command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new().content(&scoreboard),
+                    ),
+                )
+                .await
+            {
+                warn!("Error sending scoreboard: {}", e);
+                return Err(Box::new(e));
+            }
+        }
+        Err(e) => {
+            warn!("Failed to fetch scoreboard: {}", e);
+            if let Err(why) = command
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content("Sorry, I couldn't fetch the scoreboard right now."),
+                    ),
+                )
+                .await
+            {
+                warn!("Error sending error message: {}", why);
+                return Err(Box::new(why));
+            }
+            return Err(Box::new(e));
+        }
+    }
+    
+    Ok(())
+}
+
+pub async fn guess_quote(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+    games: &GameRegistry,
+    quotes: &QuotePool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = command.guild_id else {
+        return reply_ephemeral(&ctx, command, "Guessquote can only be played in servers.").await;
+    };
+
+    let options = subcommand_options(command);
+    let mode = GameMode::from_options(&options);
+    let rules = GUESSQUOTE_RULES.get().cloned().unwrap_or_default().with_overrides(&options);
+    let requested_difficulty = Difficulty::from_options(&options);
+    let rounds = matches::rounds_from_options(&options);
+    let thread = threads::from_options(&options);
+
+    info!("Starting new quote game in guild {} (mode: {:?}, rounds: {}, thread: {}, difficulty: {:?}, rules: {:?}). Allowed users: {:?}",
+        guild_id, mode, rounds, thread, requested_difficulty, rules, allowed_users(guild_id));
+
+    // Claim the channel so a second game can't collect the same answers
+    let channel_id = command.channel_id;
+    let Some(round_guard) = games.try_start(channel_id, RoundState::new(command.user.id, mode.name(), rules.duration(), rounds)) else {
+        info!("Refusing to start a second round in channel {}", channel_id);
+        return reply_ephemeral(
+            &ctx,
+            command,
+            "A guessquote round is already running in this channel. Check it with `/guessquote status`.",
+        ).await;
+    };
+    let round = round_guard.state();
+
+    // Several quotes in a row make a match, which gets its own result record
+    let match_record = if rounds > 1 {
+        let new_match = matches::NewMatch { guild_id, channel_id, started_by: command.user.id, mode: mode.name(), rounds };
+        Some(matches::record_match_start(db_pool, new_match).await)
+    } else {
+        None
+    };
+
+    let mut standings = Standings::default();
+    let mut rounds_played = 0;
+    let mut failure = None;
+    for number in 1..=rounds {
+        if number > 1 {
+            // Leave the standings up for a moment, unless the match gets cancelled meanwhile
+            tokio::select! {
+                _ = tokio::time::sleep(matches::INTERLUDE) => {}
+                _ = round.cancelled() => {}
+            }
+            if let Some(cancelled_by) = round.cancelled_by() {
+                let response = format!("🛑 Match cancelled by <@{}> after {} of {} rounds.", cancelled_by, rounds_played, rounds);
+                if let Err(e) = channel_id.say(&ctx.http, response).await {
+                    warn!("Error sending response: {}", e);
+                }
+                break;
+            }
+            round.start_next_round();
+        }
+
+        let setup = RoundSetup {
+            guild_id,
+            mode,
+            rules: &rules,
+            difficulty: requested_difficulty,
+            number,
+            rounds,
+            match_id: match_record.as_ref().map(|record| record.id.as_str()),
+            thread,
+        };
+        let round_points = match play_round(&ctx, command, db_pool, quotes, round, &setup).await {
+            Ok(Some(round_points)) => round_points,
+            Ok(None) => break,
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        };
+        rounds_played += 1;
+        for (user_id, points) in round_points {
+            standings.add(user_id, points);
+        }
+
+        if rounds > 1 {
+            let summary = if number < rounds {
+                format!(
+                    "📊 **Standings after round {} of {}:**\n{}\n\nNext quote in {} seconds!",
+                    number, rounds, standings.render(false), matches::INTERLUDE.as_secs()
+                )
+            } else {
+                format!("🏆 **Final standings:**\n{}", standings.render(true))
+            };
+            if let Err(e) = channel_id.say(&ctx.http, summary).await {
+                warn!("Error sending standings: {}", e);
+            }
+        }
+    }
+
+    if let Some(record) = &match_record {
+        let cancelled = round.cancelled_by().is_some() || failure.is_some();
+        matches::record_match_end(db_pool, record, rounds_played, cancelled, &standings).await;
+    }
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Plays a single quote: posts it, collects the guesses and scores them. Returns
+/// the points each player got, or `None` when the round was cancelled.
+async fn play_round(
+    ctx: &serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+    quotes: &QuotePool,
+    round: &RoundState,
+    setup: &RoundSetup<'_>,
+) -> Result<Option<Vec<(UserId, i32)>>, Box<dyn std::error::Error + Send + Sync>> {
+    let RoundSetup { guild_id, mode, rules, difficulty: requested_difficulty, number, rounds, match_id, thread } = *setup;
+    let allowed_users = allowed_users(guild_id);
+    let button_mode = mode == GameMode::Buttons;
+    let channel_id = command.channel_id;
+
+    // Finishing a quote needs enough of it to leave some context around the blanks
+    let min_length = if mode == GameMode::Blanks {
+        rules.min_length.max(blanks::MIN_QUOTE_LENGTH)
+    } else {
+        rules.min_length
+    };
+
+    // Pick from the cached pool, then load just that quote
+    let filter = QuoteFilter { min_length, difficulty: requested_difficulty, excluded_authors: Vec::new() };
+    let mut blanked = None;
+    let mut quote_options = Vec::new();
+    let mut result = Err(sqlx::Error::RowNotFound);
+    // Set when the requested difficulty band has no quote fit for the round
+    let mut band_empty = false;
+    for _ in 0..PICK_ATTEMPTS {
+        result = match quotes.pick(db_pool, guild_id, command.channel_id, &filter).await {
+            Ok(Some(quote_id)) => {
+                sqlx::query_as::<_, (i64, i64, String, String, chrono::DateTime<Utc>, i32, i32, i64, i64)>(
+                    "SELECT Id, UserId, Name, Content, Timestamp, times_asked, times_identified, MessageId, ChannelId
+                     FROM wdl_database.discord_messages
+                     WHERE Id = ?",
+                )
+                    .bind(quote_id)
+                    .fetch_one(db_pool)
+                    .await
+            }
+            Ok(None) => {
+                band_empty = requested_difficulty.is_some();
+                Err(sqlx::Error::RowNotFound)
+            }
+            Err(e) => Err(e),
+        };
+
+        // Quotes without a word worth blanking can't be finished, try another one
+        if let (GameMode::Blanks, Ok(row)) = (mode, &result) {
+            blanked = blanks::blank_out(&row.3, &mut rand::rng());
+            if blanked.is_none() {
+                info!("Quote {} has no words to blank out, picking another", row.0);
+                result = Err(sqlx::Error::RowNotFound);
+                continue;
+            }
+        }
+
+        // The real quote needs enough look-alikes by other people to pick it from
+        if let (GameMode::Reverse, Ok(row)) = (mode, &result) {
+            let real = reverse::QuoteOption { id: row.0, user_id: row.1, name: row.2.clone(), content: row.3.clone() };
+            let decoys = match reverse::fetch_decoys(db_pool, i64::from(guild_id), &real, row.4, allowed_users).await {
+                Ok(decoys) => decoys,
+                Err(e) => {
+                    warn!("Failed to fetch decoy quotes: {}", e);
+                    Vec::new()
+                }
+            };
+            if decoys.len() < reverse::DECOYS {
+                info!("Quote {} has only {} decoys, picking another", row.0, decoys.len());
+                result = Err(sqlx::Error::RowNotFound);
+                continue;
+            }
+            quote_options = reverse::arrange(real, decoys, &mut rand::rng());
+        }
+        break;
+    }
+
+    match result {
+        Ok(row) => {
+            // Log the correct answer for debugging
+            let quote_difficulty = difficulty::difficulty(row.5, row.6);
+            info!("Selected quote - ID: {}, User: {} (ID: {}), Content: {:?}, Time: {}, difficulty: {:.2} ({}/{} identified)", 
+                row.0, row.2, row.1, row.3, row.4, quote_difficulty, row.6, row.5);
+
+            // The author knows their own quote, so they sit this one out
+            round.set_quoted_user(UserId::new(row.1 as u64));
+
+            // In button mode the real author is mixed in with a few decoys
+            let candidates = if button_mode {
+                fetch_candidates(db_pool, row.1, allowed_users).await
+            } else {
+                Vec::new()
+            };
+
+            // In real-or-fake mode a coin flip decides whether players see the quote or an impostor
+            let fake_text = if mode == GameMode::RealOrFake && rand::rng().random_bool(0.5) {
+                generate_fake_quote(db_pool, guild_id, row.1).await
+            } else {
+                None
+            };
+            let verdicts = [(REAL_VERDICT, "✅ Real".to_string()), (FAKE_VERDICT, "🤖 Fake".to_string())];
+            let labelled_options: Vec<(i64, String)> = quote_options.iter()
+                .zip(reverse::LABELS)
+                .map(|(option, label)| (option.id, label.to_string()))
+                .collect();
+
+            let quote_message = if let Some(blanked) = &blanked {
+                format!(
+                    "**Finish the quote!**\n\n> _{}_\n\n{} said this. You have {} seconds to fill in the {} missing word{}! Reply with the words in any order, small typos are fine.\n-# {}",
+                    blanked.text,
+                    row.2,
+                    rules.duration_secs,
+                    blanked.answers.len(),
+                    if blanked.answers.len() == 1 { "" } else { "s" },
+                    rules.describe()
+                )
+            } else if mode == GameMode::Reverse {
+                let listed: Vec<String> = quote_options.iter()
+                    .zip(reverse::LABELS)
+                    .map(|(option, label)| format!("**{}.** _{}_", label, reverse::preview(&option.content)))
+                    .collect();
+                format!(
+                    "**Which of these did {} say?**\n\n{}\n\nYou have {} seconds to pick an answer below! You only get one guess.\n-# {}",
+                    row.2, listed.join("\n"), rules.duration_secs, rules.describe()
+                )
+            } else if mode == GameMode::RealOrFake {
+                format!(
+                    "**Real or fake?**\n\n> _{}_\n\nDid {} really say this, or was it made up from their messages? You have {} seconds to vote below! You only get one vote.\n-# {}",
+                    fake_text.as_deref().unwrap_or(&row.3), row.2, rules.duration_secs, rules.describe()
+                )
+            } else if mode == GameMode::When {
+                format!(
+                    "**When was this said?**\n\n> _{}_\n\nYou have {} seconds to guess! Reply with a year like `2023` or a month like `May 2023`, the closer the better.\n-# {}",
+                    row.3, rules.duration_secs, rules.describe()
+                )
+            } else if button_mode {
+                format!(
+                    "**Guess who said this quote:**\n\n> _{}_\n\nYou have {} seconds to pick an answer below! You only get one guess.\n-# Difficulty: {} | {}",
+                    row.3, rules.duration_secs, Difficulty::of(quote_difficulty).label(), rules.describe()
+                )
+            } else {
+                format!(
+                    "**Guess who said this quote:**\n\n> _{}_\n\nYou have {} seconds to guess! Mention the user with @username.\n-# Difficulty: {} | {}",
+                    row.3, rules.duration_secs, Difficulty::of(quote_difficulty).label(), rules.describe()
+                )
+            };
+
+            let quote_message = if mode.answered_by_message() {
+                format!("{}\n-# {}", quote_message, fairplay::EDITS_IGNORED)
+            } else {
+                quote_message
+            };
+            let quote_message = if rounds > 1 {
+                format!("-# Round {} of {}\n{}", number, rounds, quote_message)
+            } else {
+                quote_message
+            };
+            let round_hints = hints::available(mode);
+            let mut components = if button_mode {
+                candidate_buttons(&candidates, None)
+            } else if mode == GameMode::RealOrFake {
+                candidate_buttons(&verdicts, None)
+            } else if mode == GameMode::Reverse {
+                candidate_buttons(&labelled_options, None)
+            } else {
+                Vec::new()
+            };
+            let wager_menu = wagers::menu(rules);
+            let has_menus = !round_hints.is_empty() || wager_menu.is_some();
+            components.extend(hints::buttons(round_hints, rules));
+            components.extend(wager_menu);
+
+            // In a round thread only a pointer goes in the channel, the quote and its answers stay in the thread
+            let round_thread = if thread {
+                match threads::open(ctx, command, number == 1, threads::name(number, rounds)).await {
+                    Ok(round_thread) => round_thread,
+                    Err(why) => {
+                        warn!("Error sending thread starter: {}", why);
+                        return Err(Box::new(why));
+                    }
+                }
+            } else {
+                None
+            };
+            let round_channel = round_thread.unwrap_or(channel_id);
+
+            // Send the quote, as the command reply for the first round of a game unless the starter took it
+            let as_reply = number == 1 && !thread;
+            let quote_post = match QuotePost::send(ctx, command, round_channel, as_reply, quote_message, components).await {
+                Ok(quote_post) => quote_post,
+                Err(why) => {
+                    warn!("Error sending quote: {}", why);
+                    return Err(Box::new(why));
+                }
+            };
+
+            let round_record = history::record_round_start(
+                db_pool,
+                history::NewRound {
+                    guild_id: i64::from(guild_id),
+                    channel_id: i64::from(channel_id),
+                    started_by: i64::from(command.user.id),
+                    message_id: row.0,
+                    quoted_user_id: row.1,
+                    mode: mode.name(),
+                    match_id,
+                },
+                rules,
+            ).await;
+
+            // Collect all guesses until the round timer runs out, answering hints and wagers meanwhile
+            let start_time = std::time::Instant::now();
+            let round_message = if has_menus { Some(quote_post.message_id(ctx).await?) } else { None };
+            let hinted_quote = hints::HintedQuote {
+                guild_id: i64::from(guild_id),
+                channel_id: row.8,
+                said_at: row.4,
+                author_name: &row.2,
+                hints: round_hints,
+            };
+            let serve_hints = async {
+                match round_message {
+                    Some(message_id) if !round_hints.is_empty() => {
+                        hints::serve(ctx, db_pool, round, message_id, &hinted_quote, start_time, rules).await
+                    }
+                    _ => Vec::new(),
+                }
+            };
+            let serve_wagers = async {
+                match round_message {
+                    Some(message_id) => wagers::serve(ctx, db_pool, round, message_id, guild_id, start_time, rules).await,
+                    None => HashMap::new(),
+                }
+            };
+            let collect_guesses = async { Ok::<_, Box<dyn std::error::Error + Send + Sync>>(match mode {
+                GameMode::Buttons => {
+                    collect_button_guesses(ctx, &quote_post, round, &candidates, row.1, start_time, rules.duration()).await?
+                }
+                GameMode::Text => {
+                    let text_candidates = load_text_candidates(ctx, db_pool, guild_id, row.1, allowed_users).await;
+                    collect_text_guesses(ctx, round_channel, round, &text_candidates, row.1, start_time, rules).await
+                }
+                GameMode::When => {
+                    collect_when_guesses(ctx, round_channel, round, row.4.date_naive(), start_time, rules.duration()).await
+                }
+                GameMode::Reverse => {
+                    let picks = collect_button_guesses(ctx, &quote_post, round, &labelled_options, row.0, start_time, rules.duration()).await?;
+                    picks.into_iter()
+                        .map(|pick| {
+                            let picked = quote_options.iter()
+                                .zip(reverse::LABELS)
+                                .find(|(option, _)| Some(option.id) == pick.guessed_user_id);
+                            Guess {
+                                // The player picked a quote, not an author, so no author is recorded
+                                guessed_user_id: None,
+                                detail: picked.map(|(_, label)| GuessDetail::Picked(label)),
+                                ..pick
+                            }
+                        })
+                        .collect()
+                }
+                GameMode::RealOrFake => {
+                    let answer = if fake_text.is_some() { FAKE_VERDICT } else { REAL_VERDICT };
+                    let votes = collect_button_guesses(ctx, &quote_post, round, &verdicts, answer, start_time, rules.duration()).await?;
+                    votes.into_iter()
+                        .map(|vote| Guess {
+                            guessed_user_id: None,
+                            detail: Some(GuessDetail::Verdict { voted_real: vote.guessed_user_id == Some(REAL_VERDICT) }),
+                            ..vote
+                        })
+                        .collect()
+                }
+                GameMode::Blanks => {
+                    let answers = blanked.as_ref().map(|blanked| blanked.answers.as_slice()).unwrap_or(&[]);
+                    collect_blank_guesses(ctx, round_channel, round, answers, start_time, rules).await
+                }
+            }) };
+            let (guesses, hints_used, wagers) = tokio::join!(collect_guesses, serve_hints, serve_wagers);
+            let guesses = guesses?;
+
+            // Button collectors lock their rows, hints and wagers without them go away with the round
+            let locks_own_rows = matches!(mode, GameMode::Buttons | GameMode::RealOrFake | GameMode::Reverse);
+            if has_menus && !locks_own_rows {
+                if let Err(e) = quote_post.set_components(ctx, Vec::new()).await {
+                    warn!("Error removing hint and wager menus: {}", e);
+                }
+            }
+            let fair_play = fairplay::summary(&round.take_violations());
+
+            if let Some(cancelled_by) = round.cancelled_by() {
+                info!("Round in channel {} was cancelled, skipping scoring", channel_id);
+                for guess in guesses.iter() {
+                    history::record_guess(db_pool, &round_record, guess, 0).await;
+                }
+                wagers::settle(db_pool, guild_id, round, &round_record, wagers, true).await;
+                history::record_round_end(db_pool, &round_record, true).await;
+                let response = format!(
+                    "🛑 Round cancelled by <@{}>. The quote was from {}, no points were awarded.",
+                    cancelled_by, row.2
+                );
+                let summary = |thread_id| threads::cancelled_summary(cancelled_by, thread_id);
+                if let Err(e) = post_results(ctx, channel_id, round_thread, response, summary).await {
+                    warn!("Error sending response: {}", e);
+                }
+                return Ok(None);
+            }
+
+            let mut response = String::new();
+            
+            // First, show who said the quote with message link
+            if let Some(blanked) = &blanked {
+                response.push_str(&format!(
+                    "Time's up! {} said on {}:\n> {}\n\n",
+                    row.2, row.4.format("%Y-%m-%d"), blanked.revealed
+                ));
+            } else if mode == GameMode::Reverse {
+                let label = quote_options.iter()
+                    .zip(reverse::LABELS)
+                    .find(|(option, _)| option.id == row.0)
+                    .map(|(_, label)| label)
+                    .unwrap_or("?");
+                let others: Vec<String> = quote_options.iter()
+                    .zip(reverse::LABELS)
+                    .filter(|(option, _)| option.id != row.0)
+                    .map(|(option, label)| format!("{} was {}", label, option.name))
+                    .collect();
+                response.push_str(&format!(
+                    "Time's up! {} said **{}** on {}. [Jump to the message](https://discord.com/channels/{}/{}/{}) ({})\n\n",
+                    row.2, label, row.4.format("%Y-%m-%d"), guild_id, row.8, row.7, others.join(", ")
+                ));
+            } else if mode == GameMode::RealOrFake {
+                if fake_text.is_some() {
+                    response.push_str(&format!("Time's up! It was **fake**, made up from {}'s messages.\n\n", row.2));
+                } else {
+                    response.push_str(&format!(
+                        "Time's up! It was **real**, {} said this on {}. [Jump to the message](https://discord.com/channels/{}/{}/{})\n\n",
+                        row.2, row.4.format("%Y-%m-%d"), guild_id, row.8, row.7
+                    ));
+                }
+            } else if mode == GameMode::When {
+                response.push_str(&format!(
+                    "Time's up! {} said this on **{}** at {} UTC. [Jump to the message](https://discord.com/channels/{}/{}/{})\n\n",
+                    row.2, row.4.format("%A %-d %B %Y"), row.4.format("%H:%M"), guild_id, row.8, row.7
+                ));
+            } else {
+                response.push_str(&format!("Time's up! The quote was from {} on {} at {}\n\n", 
+                    row.2, row.4.format("%Y-%m-%d"), row.4.format("%H:%M:%S")));
+            }
+
+            response.push_str(&hints::summary(&hints_used, rules));
+
+            // Every hint used lowers what a guess in this round can earn
+            let hinted_rules = RoundRules { max_points: hints::max_points(rules, hints_used.len()), ..rules.clone() };
+            let rules = &hinted_rules;
+
+            // Handle no guesses case early
+            if guesses.is_empty() {
+                info!("No guesses received for this quote");
+                let settled = wagers::settle(db_pool, guild_id, round, &round_record, wagers, false).await;
+                history::record_round_end(db_pool, &round_record, false).await;
+                response.push_str(&wagers::summary(&settled));
+                response.push_str(&fair_play);
+                let summary = |thread_id| threads::summary(0, 0, None, thread_id);
+                if let Err(e) = post_results(ctx, channel_id, round_thread, response, summary).await {
+                    warn!("Error sending response: {}", e);
+                }
+                return Ok(Some(Vec::new()));
+            }
+            
+            info!("Processing {} guesses", guesses.len());
+            
+            // Collect results before updating scores
+            let mut correct_guesses = Vec::new();
+            let mut incorrect_guesses = Vec::new();
+            let mut round_points = Vec::new();
+            
+            let season = seasons::current_season(db_pool, guild_id).await;
+
+            // How hard this quote turned out to be in earlier rounds that asked who said
+            // it, before this round's guesses are stored
+            let quote_rating = match sqlx::query_as::<_, (i64, Option<i64>)>(
+                "SELECT COUNT(*), CAST(SUM(g.correct) AS SIGNED)
+                 FROM wdl_database.quote_guesses g
+                 JOIN wdl_database.quote_rounds r ON r.id = g.round_id
+                 WHERE g.message_id = ? AND r.mode IN ('text', 'buttons') AND r.reverted_at IS NULL",
+            )
+                .bind(row.0)
+                .fetch_one(db_pool)
+                .await
+            {
+                Ok((total, correct)) => rating::quote_rating(correct.unwrap_or(0), total),
+                Err(e) => {
+                    warn!("Failed to fetch quote history for rating: {}", e);
+                    rating::INITIAL_RATING
+                }
+            };
+
+            // Process all guesses and update scores
+            for guess in guesses.iter() {
+                let user_id = guess.user_id;
+                let is_correct = guess.is_correct;
+
+                // The streak is read and the guess booked in one transaction, so no other
+                // round can book a guess on the same streak in between
+                let season_id = season.as_ref().map(|(season_id, _)| *season_id);
+                let scored = async {
+                    let mut transaction = db_pool.begin().await?;
+
+                    // The streak only feeds the bonus of correct guesses
+                    let current_streak = if is_correct {
+                        ledger::current_streak(&mut transaction, guild_id, i64::from(user_id)).await?
+                    } else {
+                        0
+                    };
+
+                    // Points depend on how fast this particular guess arrived
+                    let points = match &guess.detail {
+                        Some(GuessDetail::When(when_guess)) => when::score_guess(when_guess, row.4.date_naive(), current_streak, rules),
+                        Some(GuessDetail::Blanks { filled, total }) => {
+                            blanks::score_guess(*filled, *total, guess.elapsed, current_streak, rules)
+                        }
+                        Some(GuessDetail::Verdict { .. } | GuessDetail::Picked(_)) => scoring::score_guess(is_correct, guess.elapsed, current_streak, 1.0, rules),
+                        None => scoring::score_guess(
+                            is_correct,
+                            guess.elapsed,
+                            current_streak,
+                            difficulty::points_multiplier(quote_difficulty),
+                            rules,
+                        ),
+                    };
+                    let entry = ledger::Entry::guess(guild_id, i64::from(user_id), season_id, &round_record.id, is_correct, points.total());
+                    ledger::append(&mut transaction, &entry).await?;
+                    transaction.commit().await?;
+                    Ok::<_, sqlx::Error>(points)
+                }.await;
+                let points = match scored {
+                    Ok(points) => points,
+                    Err(e) => {
+                        warn!("Failed to book the guess of user {}: {}", user_id, e);
+                        continue;
+                    }
+                };
+                let final_points = points.total();
+                info!(
+                    "Points breakdown for user {} - elapsed: {:.2}s, base: {}, streak_bonus: {}, final: {}, is_correct: {}",
+                    user_id, guess.elapsed.as_secs_f64(), points.base, points.streak_bonus, final_points, is_correct
+                );
+                round_points.push((user_id, final_points));
+
+                // Get updated stats for the user
+                let stats_query = "
+                    SELECT correct_guesses, total_attempts, points, current_streak, best_streak,
+                           CAST((correct_guesses * 100.0 / total_attempts) AS DOUBLE) as accuracy
+                    FROM wdl_database.quote_scores
+                    WHERE guild_id = ? AND user_id = ?";
+
+                let stats = sqlx::query_as::<_, (i32, i32, i32, i32, i32, f64)>(stats_query)
+                    .bind(i64::from(guild_id))
+                    .bind(user_id.to_string().parse::<i64>().unwrap())
+                    .fetch_one(db_pool)
+                    .await;
+
+                let user_result = match stats {
+                    Ok((correct, total, _total_points, current_streak, best_streak, accuracy)) => {
+                        let streak_text = if current_streak > 0 {
+                            format!(" | 🔥 Streak: {} (Best: {})", current_streak, best_streak)
+                        } else {
+                            String::new()
+                        };
+                        
+                        info!("Updated stats for user {}: {}/{} correct, round points: {} (streak: {}/{}), {}% accuracy",
+                            user_id, correct, total, final_points, current_streak, best_streak, accuracy.round());
+                        let answer_text = match &guess.detail {
+                            Some(detail) => format!(" ({})", detail.describe()),
+                            None => String::new(),
+                        };
+                        format!(
+                            "<@{}>{} - {} points! {} correct out of {} attempts ({}% accuracy){}",
+                            user_id,
+                            answer_text,
+                            if final_points >= 0 { format!("+{}", final_points) } else { final_points.to_string() },
+                            correct,
+                            total,
+                            accuracy.round(),
+                            streak_text
+                        )
+                    }
+                    Err(e) => {
+                        warn!("Failed to fetch user stats: {}", e);
+                        format!("<@{}>", user_id)
+                    }
+                };
+
+                history::record_guess(db_pool, &round_record, guess, final_points).await;
+
+                if is_correct {
+                    correct_guesses.push(user_result);
+                } else {
+                    incorrect_guesses.push(user_result);
+                }
+            }
+            let settled = wagers::settle(db_pool, guild_id, round, &round_record, wagers, false).await;
+            for wager in settled.iter().filter(|wager| wager.payout() != 0) {
+                match round_points.iter_mut().find(|(user_id, _)| *user_id == wager.wager.user_id) {
+                    Some((_, points)) => *points += wager.payout(),
+                    None => round_points.push((wager.wager.user_id, wager.payout())),
+                }
+            }
+            history::record_round_end(db_pool, &round_record, false).await;
+            // Ratings and quote difficulty are about recognising who said something
+            let rating_lines = if mode.asks_who() {
+                difficulty::record_round(db_pool, row.0, guesses.iter().any(|guess| guess.is_correct)).await;
+                update_ratings(db_pool, guild_id, quote_rating, &guesses).await
+            } else {
+                Vec::new()
+            };
+
+            // Add correct guesses to response
+            if !correct_guesses.is_empty() {
+                response.push_str("🎉 **Correct Guesses:**\n");
+                for guess in correct_guesses {
+                    response.push_str(&format!("✅ {}\n", guess));
+                }
+                response.push('\n');
+            }
+
+            // Add incorrect guesses to response
+            if !incorrect_guesses.is_empty() {
+                response.push_str("❌ **Incorrect Guesses:**\n");
+                for guess in incorrect_guesses {
+                    response.push_str(&format!("❌ {}\n", guess));
+                }
+            }
+
+            if !rating_lines.is_empty() {
+                response.push_str("\n📈 **Rating changes:**\n");
+                for line in rating_lines {
+                    response.push_str(&format!("{}\n", line));
+                }
+            }
+
+            response.push_str(&wagers::summary(&settled));
+            response.push_str(&fair_play);
+
+            let correct = guesses.iter().filter(|guess| guess.is_correct).count();
+            let best = round_points.iter().copied().max_by_key(|&(_, points)| points);
+            let summary = |thread_id| threads::summary(guesses.len(), correct, best, thread_id);
+            if let Err(e) = post_results(ctx, channel_id, round_thread, response, summary).await {
+                warn!("Error sending response: {}", e);
+                return Err(Box::new(e));
+            }
+            
+            Ok(Some(round_points))
+        }
+        Err(e) => {
+            warn!("Failed to execute query: {}", e);
+            let apology = match requested_difficulty {
+                Some(band) if band_empty => format!("There are no {} quotes for this round yet, try another difficulty.", band.label()),
+                _ => "Sorry, I couldn't fetch a quote right now.".to_string(),
+            };
+            let sent = if number == 1 {
+                command
+                    .create_response(
+                        &ctx.http,
+                        CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(apology)),
+                    )
+                    .await
+            } else {
+                channel_id.say(&ctx.http, apology).await.map(|_| ())
+            };
+            if let Err(why) = sent {
+                warn!("Error sending error message: {}", why);
+                return Err(Box::new(why));
+            }
+            Err(Box::new(e))
+        }
+    }
+}
+
+/// Posts the results of a round where it was played. A round thread is archived
+/// afterwards and the channel gets the one-line summary pointing to it instead.
+async fn post_results(
+    ctx: &serenity::client::Context,
+    channel_id: ChannelId,
+    round_thread: Option<ChannelId>,
+    response: String,
+    summary: impl FnOnce(ChannelId) -> String,
+) -> Result<(), serenity::Error> {
+    let Some(thread_id) = round_thread else {
+        return channel_id.say(&ctx.http, response).await.map(|_| ());
+    };
+
+    let sent = thread_id.say(&ctx.http, response).await;
+    threads::close(ctx, thread_id).await;
+    sent?;
+    channel_id.say(&ctx.http, summary(thread_id)).await.map(|_| ())
+}
+
+/// Updates the skill rating of everyone who guessed in the round and returns a
+/// summary line per player. Must run after their `quote_scores` rows exist.
+async fn update_ratings(db_pool: &MySqlPool, guild_id: GuildId, quote_rating: f64, guesses: &[Guess]) -> Vec<String> {
+    // One result per player: right first time, right after wrong tries, or never right
+    let mut outcomes: Vec<(i64, f64)> = Vec::new();
+    for guess in guesses {
+        let user_id = i64::from(guess.user_id);
+        match outcomes.iter_mut().find(|(id, _)| *id == user_id) {
+            Some((_, score)) if guess.is_correct && *score == 0.0 => *score = 0.5,
+            Some(_) => {}
+            None => outcomes.push((user_id, if guess.is_correct { 1.0 } else { 0.0 })),
+        }
+    }
+    if outcomes.is_empty() {
+        return Vec::new();
+    }
+
+    let mut query_builder = sqlx::QueryBuilder::new("SELECT user_id, rating, rated_rounds FROM wdl_database.quote_scores WHERE guild_id = ");
+    query_builder.push_bind(i64::from(guild_id));
+    query_builder.push(" AND user_id IN (");
+    let mut separated = query_builder.separated(", ");
+    for (user_id, _) in outcomes.iter() {
+        separated.push_bind(*user_id);
+    }
+    separated.push_unseparated(")");
+
+    let current: HashMap<i64, (f64, i32)> = match query_builder.build_query_as::<(i64, f64, i32)>().fetch_all(db_pool).await {
+        Ok(rows) => rows.into_iter().map(|(user_id, rating, rated_rounds)| (user_id, (rating, rated_rounds))).collect(),
+        Err(e) => {
+            warn!("Failed to fetch ratings: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let results: Vec<rating::RoundResult> = outcomes.iter()
+        .map(|(user_id, score)| {
+            let (rating, rated_rounds) = current.get(user_id).copied().unwrap_or((rating::INITIAL_RATING, 0));
+            rating::RoundResult { rating, rated_rounds, score: *score }
+        })
+        .collect();
+    let changes = rating::rating_changes(quote_rating, &results);
+
+    let mut lines = Vec::new();
+    for (((user_id, _), result), change) in outcomes.iter().zip(results.iter()).zip(changes) {
+        let new_rating = result.rating + change;
+        info!("Rating for user {} in guild {}: {:.1} -> {:.1} (quote rating {:.1})", user_id, guild_id, result.rating, new_rating, quote_rating);
+
+        if let Err(e) = sqlx::query(
+            "UPDATE wdl_database.quote_scores SET rating = ?, rated_rounds = rated_rounds + 1 WHERE guild_id = ? AND user_id = ?",
+        )
+            .bind(new_rating)
+            .bind(i64::from(guild_id))
+            .bind(user_id)
+            .execute(db_pool)
+            .await
+        {
+            warn!("Failed to update rating for user {}: {}", user_id, e);
+            continue;
+        }
+
+        let change = change.round() as i64;
+        lines.push(format!(
+            "<@{}>: {} → {} ({}{})",
+            user_id,
+            result.rating.round(),
+            new_rating.round(),
+            if change >= 0 { "+" } else { "" },
+            change
+        ));
+    }
+    lines
+}
+
+/// Looks up the real author plus up to three decoys from the allowed quote users
+/// and returns them shuffled as `(user_id, name)` pairs.
+async fn fetch_candidates(
+    db_pool: &MySqlPool,
+    author_id: i64,
+    allowed_users: &[i64],
+) -> Vec<(i64, String)> {
+    let mut decoys: Vec<i64> = allowed_users.iter()
+        .copied()
+        .filter(|&id| id != author_id)
+        .collect();
+    decoys.shuffle(&mut rand::rng());
+    decoys.truncate(3);
+
+    let mut candidate_ids = decoys;
+    candidate_ids.push(author_id);
+    candidate_ids.shuffle(&mut rand::rng());
+
+    let names = fetch_latest_names(db_pool, &candidate_ids).await;
+
+    candidate_ids.into_iter()
+        .map(|id| {
+            let name = names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+            (id, name)
+        })
+        .collect()
+}
+
+/// Looks up the most recent archived name of each user.
+async fn fetch_latest_names(db_pool: &MySqlPool, user_ids: &[i64]) -> HashMap<i64, String> {
+    if user_ids.is_empty() {
+        return HashMap::new();
+    }
+
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "SELECT UserId, Name FROM (
+            SELECT UserId, Name,
+                   ROW_NUMBER() OVER (PARTITION BY UserId ORDER BY Timestamp DESC) as rn
+            FROM wdl_database.discord_messages
+            WHERE UserId IN (",
+    );
+    let mut separated = query_builder.separated(", ");
+    for &id in user_ids.iter() {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(")) latest_names WHERE rn = 1");
+
+    match query_builder
+        .build_query_as::<(i64, String)>()
+        .fetch_all(db_pool)
+        .await
+    {
+        Ok(rows) => rows.into_iter().collect(),
+        Err(e) => {
+            warn!("Failed to fetch latest names: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Makes up a message in the style of the author, trained on their archived
+/// messages in this guild. Returns `None` when they haven't said enough to imitate.
+async fn generate_fake_quote(db_pool: &MySqlPool, guild_id: GuildId, author_id: i64) -> Option<String> {
+    let messages = match sqlx::query_as::<_, (String,)>(
+        "SELECT Content FROM wdl_database.discord_messages
+         WHERE GuildId = ? AND UserId = ?
+         ORDER BY Id DESC
+         LIMIT ?",
+    )
+        .bind(i64::from(guild_id))
+        .bind(author_id)
+        .bind(MARKOV_TRAINING_MESSAGES)
+        .fetch_all(db_pool)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!("Failed to fetch messages to imitate user {}: {}", author_id, e);
+            return None;
+        }
+    };
+
+    let chain = MarkovChain::train(messages.iter().map(|(content,)| content.as_str()));
+    let seed: u64 = rand::rng().random();
+    let fake = chain.generate(seed);
+    info!("Generated fake quote for user {} from {} messages with seed {}: {:?}", author_id, messages.len(), seed, fake);
+    fake
+}
+
+/// Gathers every name a free-text guess may use for the possible authors: their
+/// names in the archive, their nickname in this guild and any aliases set by admins.
+async fn load_text_candidates(
+    ctx: &serenity::client::Context,
+    db_pool: &MySqlPool,
+    guild_id: GuildId,
+    author_id: i64,
+    allowed_users: &[i64],
+) -> Vec<Candidate> {
+    let mut user_ids = allowed_users.to_vec();
+    if !user_ids.contains(&author_id) {
+        user_ids.push(author_id);
+    }
+
+    let mut names: HashMap<i64, Vec<String>> = aliases::fetch_aliases(db_pool, guild_id, &user_ids).await;
+
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "SELECT DISTINCT UserId, Name FROM wdl_database.discord_messages WHERE UserId IN (",
+    );
+    let mut separated = query_builder.separated(", ");
+    for &id in user_ids.iter() {
+        separated.push_bind(id);
+    }
+    separated.push_unseparated(")");
+
+    match query_builder.build_query_as::<(i64, String)>().fetch_all(db_pool).await {
+        Ok(rows) => {
+            for (user_id, name) in rows {
+                names.entry(user_id).or_default().push(name);
+            }
+        }
+        Err(e) => warn!("Failed to fetch archive names: {}", e),
+    }
+
+    let members = join_all(user_ids.iter().map(|&id| guild_id.member(&ctx.http, UserId::new(id as u64)))).await;
+    for member in members.into_iter().flatten() {
+        let entry = names.entry(i64::from(member.user.id)).or_default();
+        entry.extend(member.nick.clone());
+        entry.extend(member.user.global_name.clone());
+        entry.push(member.user.name.clone());
+    }
+
+    user_ids.into_iter()
+        .map(|user_id| {
+            let mut user_names = names.remove(&user_id).unwrap_or_default();
+            user_names.sort();
+            user_names.dedup();
+            Candidate { user_id, names: user_names }
+        })
+        .collect()
+}
+
+/// Builds the answer buttons for a button-mode round. Once the round is over the
+/// correct author is passed in, which disables every button and highlights the answer.
+fn candidate_buttons(candidates: &[(i64, String)], correct_user_id: Option<i64>) -> Vec<CreateActionRow> {
+    let buttons = candidates.iter()
+        .map(|(user_id, name)| {
+            let style = match correct_user_id {
+                Some(correct) if correct == *user_id => ButtonStyle::Success,
+                _ => ButtonStyle::Secondary,
+            };
+            CreateButton::new(format!("{}{}", GUESS_BUTTON_PREFIX, user_id))
+                .label(name.chars().take(80).collect::<String>())
+                .style(style)
+                .disabled(correct_user_id.is_some())
+        })
+        .collect();
+
+    vec![CreateActionRow::Buttons(buttons)]
+}
+
+/// Collects button presses on the round message. Every player gets exactly one
+/// locked-in answer; later presses only get a reminder of what they picked.
+async fn collect_button_guesses(
+    ctx: &serenity::client::Context,
+    quote_post: &QuotePost<'_>,
+    round: &RoundState,
+    candidates: &[(i64, String)],
+    correct_user_id: i64,
+    start_time: std::time::Instant,
+    duration: Duration,
+) -> Result<Vec<Guess>, Box<dyn std::error::Error + Send + Sync>> {
+    let round_message_id = quote_post.message_id(ctx).await?;
+    let names: HashMap<i64, &str> = candidates.iter()
+        .map(|(id, name)| (*id, name.as_str()))
+        .collect();
+
+    let mut guesses = Vec::new();
+    let mut answers: HashMap<UserId, i64> = HashMap::new();
+
+    let remaining = duration.saturating_sub(start_time.elapsed());
+    let mut interactions = ComponentInteractionCollector::new(&ctx.shard)
+        .message_id(round_message_id)
+        .filter(|interaction| interaction.data.custom_id.starts_with(GUESS_BUTTON_PREFIX))
+        .timeout(remaining)
+        .stream();
+
+    loop {
+        let interaction = tokio::select! {
+            interaction = interactions.next() => interaction,
+            _ = round.cancelled() => None,
+        };
+        let Some(interaction) = interaction else {
+            break;
+        };
+
+        let picked = interaction.data.custom_id
+            .strip_prefix(GUESS_BUTTON_PREFIX)
+            .and_then(|id| id.parse::<i64>().ok());
+
+        let Some(picked) = picked else {
+            warn!("Ignoring unknown guessquote component: {}", interaction.data.custom_id);
+            continue;
+        };
+
+        let answer = fairplay::Answer {
+            author_id: interaction.user.id,
+            is_bot: interaction.user.bot,
+            names_several: false,
+        };
+        let reply = if !round.may_answer(interaction.user.id) {
+            "Only the players of this round can answer.".to_string()
+        } else if let Some(violation) = round.check_fair_play(&answer) {
+            format!("Your answer doesn't count: {}.", violation.describe())
+        } else if let Some(previous) = answers.get(&interaction.user.id) {
+            info!("Skipping duplicate guess from user {}", interaction.user.id);
+            format!("You already locked in **{}**.", names.get(previous).copied().unwrap_or("an answer"))
+        } else {
+            let is_correct = picked == correct_user_id;
+            info!("Button guess from {} - picked: {}, is_correct: {}", interaction.user.id, picked, is_correct);
+            answers.insert(interaction.user.id, picked);
+            let elapsed = start_time.elapsed();
+            guesses.push(Guess {
+                user_id: interaction.user.id,
+                guessed_user_id: Some(picked),
+                is_correct,
+                elapsed,
+                detail: None,
+            });
+            round.record_guess(interaction.user.id, elapsed, is_correct);
+            format!("Locked in **{}**. Results come when time's up!", names.get(&picked).copied().unwrap_or("your answer"))
+        };
+
+        if let Err(e) = interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new().content(reply).ephemeral(true),
+                ),
+            )
+            .await
+        {
+            warn!("Error acknowledging guess button: {}", e);
+        }
+
+        // Rounds for a fixed set of players end as soon as all of them answered
+        if round.all_answered(answers.len()) {
+            break;
+        }
+    }
+
+    // Lock the buttons and highlight the real author
+    if let Err(e) = quote_post.set_components(ctx, candidate_buttons(candidates, Some(correct_user_id))).await {
+        warn!("Error disabling guess buttons: {}", e);
+    }
+
+    Ok(guesses)
+}
+
+/// Collects free-text replies in the channel. A mention of the author or one of
+/// their names counts as correct; wrong guessers may keep trying until time runs
+/// out, unless the round is hardcore.
+async fn collect_text_guesses(
+    ctx: &serenity::client::Context,
+    channel_id: ChannelId,
+    round: &RoundState,
+    candidates: &[Candidate],
+    correct_user_id: i64,
+    start_time: std::time::Instant,
+    rules: &RoundRules,
+) -> Vec<Guess> {
+    let mut guesses = Vec::new();
+    let mut guessed_users = HashSet::new();
+
+    while start_time.elapsed() < rules.duration() && round.cancelled_by().is_none() {
+        if let Some(guess) = channel_id
+            .await_reply(&ctx.shard)
+            .timeout(Duration::from_secs(1))
+            .await 
+        {
+            // The bot's own messages, like hint reveals, are part of the round, not answers
+            if guess.author.id == ctx.cache.current_user().id {
+                continue;
+            }
+
+            // Skip if user has already guessed
+            if guessed_users.contains(&guess.author.id) {
+                info!("Skipping duplicate guess from user {}", guess.author.id);
+                continue;
+            }
+            
+            info!("Processing guess from {} - content: {:?}", guess.author.id, guess.content);
+            
+            // Work out which candidate the guess names, a guess naming several is wrong
+            let mentioned_ids: Vec<i64> = guess.mentions.iter().map(|user| i64::from(user.id)).collect();
+            let guess_match = matching::match_guess(&guess.content, &mentioned_ids, candidates);
+            let is_correct = guess_match == GuessMatch::Single(correct_user_id);
+
+            // Chat that names nobody isn't held to the fair-play rules, it can't give anything away
+            if guess_match != GuessMatch::NoMatch {
+                let answer = fairplay::Answer {
+                    author_id: guess.author.id,
+                    is_bot: guess.author.bot,
+                    names_several: matches!(guess_match, GuessMatch::Ambiguous(_)),
+                };
+                if round.check_fair_play(&answer).is_some_and(|violation| violation.drops_guess()) {
+                    continue;
+                }
+            }
+            
+            info!("Guess analysis - match: {:?}, is_correct: {}", guess_match, is_correct);
+            
+            // Store the guess result along with when it arrived
+            let elapsed = start_time.elapsed();
+            guesses.push(Guess {
+                user_id: guess.author.id,
+                guessed_user_id: match guess_match {
+                    GuessMatch::Single(user_id) => Some(user_id),
+                    _ => None,
+                },
+                is_correct,
+                elapsed,
+                detail: None,
+            });
+            round.record_guess(guess.author.id, elapsed, is_correct);
+            
+            // Only mark user as having guessed if they got it right, hardcore allows one guess
+            if is_correct {
+                info!("Correct guess from user {}", guess.author.id);
+                guessed_users.insert(guess.author.id);
+            } else if rules.hardcore {
+                info!("Wrong guess from user {} uses up their only hardcore guess", guess.author.id);
+                guessed_users.insert(guess.author.id);
+            }
+        }
+    }
+
+    guesses
+}
+
+/// The fair-play view of a reply that can't name several candidates.
+fn message_answer(message: &Message) -> fairplay::Answer {
+    fairplay::Answer {
+        author_id: message.author.id,
+        is_bot: message.author.bot,
+        names_several: false,
+    }
+}
+
+/// Collects year or month guesses in the channel, one per player. Messages that
+/// don't read as a date are left alone as chat.
+async fn collect_when_guesses(
+    ctx: &serenity::client::Context,
+    channel_id: ChannelId,
+    round: &RoundState,
+    said_on: chrono::NaiveDate,
+    start_time: std::time::Instant,
+    duration: Duration,
+) -> Vec<Guess> {
+    let mut guesses = Vec::new();
+    let mut guessed_users = HashSet::new();
+
+    while start_time.elapsed() < duration && round.cancelled_by().is_none() {
+        let Some(message) = channel_id
+            .await_reply(&ctx.shard)
+            .timeout(Duration::from_secs(1))
+            .await
+        else {
+            continue;
+        };
+        if message.author.id == ctx.cache.current_user().id {
+            continue;
+        }
+        let Some(when_guess) = when::parse_guess(&message.content) else {
+            continue;
+        };
+        if round.check_fair_play(&message_answer(&message)).is_some() {
+            continue;
+        }
+        if !guessed_users.insert(message.author.id) {
+            info!("Skipping duplicate guess from user {}", message.author.id);
+            continue;
+        }
+
+        let is_correct = when_guess.is_correct(said_on);
+        info!("When guess from {} - guess: {:?}, is_correct: {}", message.author.id, when_guess, is_correct);
+        let elapsed = start_time.elapsed();
+        guesses.push(Guess {
+            user_id: message.author.id,
+            guessed_user_id: None,
+            is_correct,
+            elapsed,
+            detail: Some(GuessDetail::When(when_guess)),
+        });
+        round.record_guess(message.author.id, elapsed, is_correct);
+    }
+
+    guesses
+}
+
+/// Collects attempts at the missing words of a "finish the quote" round. Every
+/// reply is an attempt and a player's best one counts; players are done once they
+/// fill every blank, or after their first attempt in hardcore rounds.
+async fn collect_blank_guesses(
+    ctx: &serenity::client::Context,
+    channel_id: ChannelId,
+    round: &RoundState,
+    answers: &[String],
+    start_time: std::time::Instant,
+    rules: &RoundRules,
+) -> Vec<Guess> {
+    let mut guesses: Vec<Guess> = Vec::new();
+    let mut best_attempts: HashMap<UserId, (usize, usize)> = HashMap::new();
+    let mut finished_users = HashSet::new();
+
+    while start_time.elapsed() < rules.duration() && round.cancelled_by().is_none() {
+        let Some(message) = channel_id
+            .await_reply(&ctx.shard)
+            .timeout(Duration::from_secs(1))
+            .await
+        else {
+            continue;
+        };
+        if message.author.id == ctx.cache.current_user().id {
+            continue;
+        }
+        if finished_users.contains(&message.author.id) {
+            info!("Skipping extra attempt from user {}", message.author.id);
+            continue;
+        }
+        if round.check_fair_play(&message_answer(&message)).is_some() {
+            continue;
+        }
+
+        let filled = blanks::filled_blanks(&message.content, answers).into_iter().filter(|&filled| filled).count();
+        let total = answers.len();
+        info!("Blank attempt from {} - content: {:?}, filled: {}/{}", message.author.id, message.content, filled, total);
+        let guess = Guess {
+            user_id: message.author.id,
+            guessed_user_id: None,
+            is_correct: filled == total,
+            elapsed: start_time.elapsed(),
+            detail: Some(GuessDetail::Blanks { filled, total }),
+        };
+        // Wagers go by the first attempt, even when a later one replaces it below
+        round.record_guess(guess.user_id, guess.elapsed, guess.is_correct);
+
+        // Keep only the best attempt, an earlier one wins ties since it was faster
+        match best_attempts.get(&message.author.id) {
+            Some(&(index, best)) if filled > best => {
+                guesses[index] = guess;
+                best_attempts.insert(message.author.id, (index, filled));
+            }
+            Some(_) => {}
+            None => {
+                best_attempts.insert(message.author.id, (guesses.len(), filled));
+                guesses.push(guess);
+            }
+        }
+
+        if filled == total || rules.hardcore {
+            finished_users.insert(message.author.id);
+        }
+    }
+
+    guesses
+}
+
+pub async fn roll_quote(
+    ctx: serenity::client::Context,
+    _msg: &serenity::model::channel::Message,
+    channel_id: ChannelId,
+    counter: &mut usize,
+    roll_amount: usize,
+    db_pool: &MySqlPool,
+    quotes: &QuotePool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("roll_quote: Connected to {:?}", channel_id);
+
+    *counter += 1; // Increment counter
+    info!("roll_quote: counter at: {:?}", counter);
+    if *counter >= roll_amount {
+        *counter = 0; // Reset the counter
+
+        let rand = rand::rng().random_range(0..100);
+        info!("rand generated {:?}", rand);
+
+        if rand < 1 {
+            // Quotes come from the guild of the channel they are posted in
+            let guild_id = match channel_id.to_channel(&ctx.http).await.map(|channel| channel.guild()) {
+                Ok(Some(channel)) => channel.guild_id,
+                Ok(None) => {
+                    warn!("roll_quote: Channel {} is not in a guild", channel_id);
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!("roll_quote: Failed to look up channel {}: {}", channel_id, e);
+                    return Ok(());
+                }
+            };
+
+            // Pick from the cached pool, then load just that quote
+            let filter = QuoteFilter { min_length: 1, difficulty: None, excluded_authors: Vec::new() };
+            let result = match quotes.pick(db_pool, guild_id, channel_id, &filter).await {
+                Ok(Some(quote_id)) => {
+                    sqlx::query_as::<_, (i64, i64, String, String, chrono::DateTime<Utc>)>(
+                        "SELECT Id, UserId, Name, Content, Timestamp
+                         FROM wdl_database.discord_messages
+                         WHERE Id = ?",
+                    )
+                        .bind(quote_id)
+                        .fetch_one(db_pool)
+                        .await
+                }
+                Ok(None) => Err(sqlx::Error::RowNotFound),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(row) => {
+                    // Print the data
+                    info!(
+                        "Id: {}, UserId: {}, Name: {}, Content: {}, Timestamp: {}",
+                        row.0, row.1, row.2, row.3, row.4
+                    );
+
+                    // Store the string in a variable
+                    let timestamp_string = row.4.to_string();
+                    // Now split the string and collect into Vec
+                    let timestamp: Vec<_> = timestamp_string.split(" ").collect();
+                    
+                    if let Err(e) = channel_id
+                        .say(
+                            &ctx.http,
+                            &format!(
+                                "> ** <@{}> on {} at {}:**\n> \n> _'{}'_",
+                                row.1, timestamp[0], timestamp[1], row.3
+                            ),
+                        )
+                        .await
+                    {
+                        error!("roll_quote: Failed to send random quote: {}", e);
+                    }
+                }
+                Err(e) => {
+                    error!("roll_quote: Failed to execute random quote query: {}", e);
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,134 @@
+/// Rating every player starts at.
+pub const INITIAL_RATING: f64 = 1500.0;
+/// Rounds during which a player's rating moves faster to find its level.
+const PROVISIONAL_ROUNDS: i32 = 10;
+const PROVISIONAL_K: f64 = 40.0;
+const ESTABLISHED_K: f64 = 20.0;
+/// Keeps quotes nobody or everybody has identified from getting extreme ratings.
+const MAX_QUOTE_OFFSET: f64 = 600.0;
+
+/// How a player did in one round, with their rating going into it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundResult {
+    pub rating: f64,
+    pub rated_rounds: i32,
+    /// 1.0 for a right first answer, 0.5 when it took more tries, 0.0 otherwise.
+    pub score: f64,
+}
+
+/// Expected score of a player against an opponent, Elo style.
+pub fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating - rating) / 400.0))
+}
+
+/// Rates a quote like an opponent from how often it was identified before.
+/// Smoothed so a quote that was only asked once doesn't look impossible or trivial.
+pub fn quote_rating(correct_guesses: i64, total_guesses: i64) -> f64 {
+    let solve_rate = (correct_guesses.max(0) as f64 + 1.0) / (total_guesses.max(0) as f64 + 2.0);
+    let offset = 400.0 * ((1.0 - solve_rate) / solve_rate).log10();
+    INITIAL_RATING + offset.clamp(-MAX_QUOTE_OFFSET, MAX_QUOTE_OFFSET)
+}
+
+fn k_factor(rated_rounds: i32) -> f64 {
+    if rated_rounds < PROVISIONAL_ROUNDS {
+        PROVISIONAL_K
+    } else {
+        ESTABLISHED_K
+    }
+}
+
+/// Rating changes for everyone who played a round, in the order given.
+///
+/// Each player plays the quote, and when others answered too, every other
+/// player: getting a quote right that others missed is worth more than getting
+/// one right that everyone knew. Both parts weigh the same.
+pub fn rating_changes(quote_rating: f64, results: &[RoundResult]) -> Vec<f64> {
+    results.iter()
+        .enumerate()
+        .map(|(index, player)| {
+            let against_quote = player.score - expected_score(player.rating, quote_rating);
+
+            let opponents: Vec<&RoundResult> = results.iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, opponent)| opponent)
+                .collect();
+            let performance = if opponents.is_empty() {
+                against_quote
+            } else {
+                let against_players = opponents.iter()
+                    .map(|opponent| {
+                        let outcome = 0.5 + (player.score - opponent.score) / 2.0;
+                        outcome - expected_score(player.rating, opponent.rating)
+                    })
+                    .sum::<f64>() / opponents.len() as f64;
+                (against_quote + against_players) / 2.0
+            };
+
+            k_factor(player.rated_rounds) * performance
+        })
+        .collect()
+}
+
+/// Rating changes of the winner and loser of a duel, each moving at the pace
+/// of their own number of rated duels.
+pub fn duel_changes(winner_rating: f64, winner_duels: i32, loser_rating: f64, loser_duels: i32) -> (f64, f64) {
+    let winner_change = k_factor(winner_duels) * (1.0 - expected_score(winner_rating, loser_rating));
+    let loser_change = k_factor(loser_duels) * (0.0 - expected_score(loser_rating, winner_rating));
+    (winner_change, loser_change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(rating: f64, score: f64) -> RoundResult {
+        RoundResult { rating, rated_rounds: PROVISIONAL_ROUNDS, score }
+    }
+
+    #[test]
+    fn unknown_quotes_are_rated_like_a_new_player() {
+        assert_eq!(quote_rating(0, 0), INITIAL_RATING);
+        assert!(quote_rating(1, 10) > INITIAL_RATING);
+        assert!(quote_rating(9, 10) < INITIAL_RATING);
+        assert_eq!(quote_rating(0, 1_000_000), INITIAL_RATING + MAX_QUOTE_OFFSET);
+    }
+
+    #[test]
+    fn solving_a_hard_quote_gains_more() {
+        let solo = [player(INITIAL_RATING, 1.0)];
+        let easy = rating_changes(quote_rating(9, 10), &solo)[0];
+        let hard = rating_changes(quote_rating(1, 10), &solo)[0];
+        assert!(easy > 0.0);
+        assert!(hard > easy);
+    }
+
+    #[test]
+    fn beating_other_players_counts() {
+        let quote = quote_rating(0, 0);
+        let alone = rating_changes(quote, &[player(INITIAL_RATING, 1.0), player(INITIAL_RATING, 1.0)]);
+        let ahead = rating_changes(quote, &[player(INITIAL_RATING, 1.0), player(INITIAL_RATING, 0.0)]);
+        assert!(ahead[0] > alone[0]);
+        assert!(ahead[1] < 0.0);
+    }
+
+    #[test]
+    fn upsets_move_duel_ratings_more() {
+        let (even_gain, even_loss) = duel_changes(INITIAL_RATING, PROVISIONAL_ROUNDS, INITIAL_RATING, PROVISIONAL_ROUNDS);
+        assert_eq!(even_gain, ESTABLISHED_K / 2.0);
+        assert_eq!(even_loss, -ESTABLISHED_K / 2.0);
+
+        let (upset_gain, _) = duel_changes(INITIAL_RATING - 200.0, PROVISIONAL_ROUNDS, INITIAL_RATING, PROVISIONAL_ROUNDS);
+        let (expected_gain, _) = duel_changes(INITIAL_RATING + 200.0, PROVISIONAL_ROUNDS, INITIAL_RATING, PROVISIONAL_ROUNDS);
+        assert!(upset_gain > even_gain);
+        assert!(expected_gain < even_gain);
+    }
+
+    #[test]
+    fn new_players_move_faster() {
+        let quote = quote_rating(0, 0);
+        let new = rating_changes(quote, &[RoundResult { rating: INITIAL_RATING, rated_rounds: 0, score: 1.0 }])[0];
+        let established = rating_changes(quote, &[player(INITIAL_RATING, 1.0)])[0];
+        assert!(new > established);
+    }
+}
@@ -0,0 +1,201 @@
+use font8x8::legacy::{BASIC_LEGACY, LATIN_LEGACY};
+
+const GLYPH_SIZE: u32 = 8;
+const SCALE: u32 = 2;
+const CHAR_WIDTH: u32 = GLYPH_SIZE * SCALE;
+const PADDING: u32 = 8;
+const LABEL_CHARS: usize = 10;
+
+const BACKGROUND: [u8; 3] = [0x2b, 0x2d, 0x31];
+const GRID: [u8; 3] = [0x1e, 0x1f, 0x22];
+const TEXT: [u8; 3] = [0xf2, 0xf3, 0xf5];
+const MUTED_TEXT: [u8; 3] = [0xb5, 0xba, 0xc1];
+const HIT: [u8; 3] = [0x23, 0xa5, 0x5a];
+const MISS: [u8; 3] = [0xda, 0x37, 0x3c];
+
+/// A simple RGB pixel buffer with just enough drawing for stat tables.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for _ in 0..width * height {
+            pixels.extend_from_slice(&BACKGROUND);
+        }
+        Canvas { width, height, pixels }
+    }
+
+    fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                let offset = ((py * self.width + px) * 3) as usize;
+                self.pixels[offset..offset + 3].copy_from_slice(&color);
+            }
+        }
+    }
+
+    /// Draws text with the built-in 8x8 font. Characters outside Latin-1 are shown as `?`.
+    fn draw_text(&mut self, x: u32, y: u32, text: &str, color: [u8; 3]) {
+        for (index, character) in text.chars().enumerate() {
+            let glyph = glyph(character);
+            let glyph_x = x + index as u32 * CHAR_WIDTH;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..GLYPH_SIZE {
+                    if bits & (1 << column) != 0 {
+                        self.fill_rect(glyph_x + column * SCALE, y + row as u32 * SCALE, SCALE, SCALE, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+        }
+        Ok(bytes)
+    }
+}
+
+fn glyph(character: char) -> [u8; 8] {
+    let code = character as usize;
+    match code {
+        0x20..=0x7e => BASIC_LEGACY[code],
+        0xa0..=0xff => LATIN_LEGACY[code - 0xa0],
+        _ => BASIC_LEGACY['?' as usize],
+    }
+}
+
+/// Shortens a label to fit a table cell.
+fn fit_label(label: &str, max_chars: usize) -> String {
+    if label.chars().count() <= max_chars {
+        label.to_string()
+    } else {
+        let mut shortened: String = label.chars().take(max_chars - 1).collect();
+        shortened.push('.');
+        shortened
+    }
+}
+
+/// Blends from the background towards `color` by `intensity` (0.0 to 1.0).
+fn shade(color: [u8; 3], intensity: f64) -> [u8; 3] {
+    let intensity = intensity.clamp(0.0, 1.0);
+    let mut shaded = [0; 3];
+    for channel in 0..3 {
+        let from = BACKGROUND[channel] as f64;
+        let to = color[channel] as f64;
+        shaded[channel] = (from + (to - from) * intensity).round() as u8;
+    }
+    shaded
+}
+
+/// Renders a confusion matrix as a PNG. Rows are the real authors, columns who
+/// their quotes were attributed to; the diagonal holds correct answers in green
+/// and every other cell mix-ups in red, shaded by count.
+pub fn render_confusion_table(title: &str, labels: &[String], counts: &[Vec<i64>]) -> Result<Vec<u8>, png::EncodingError> {
+    let label_width = LABEL_CHARS as u32 * CHAR_WIDTH + PADDING * 2;
+    let cell_width = label_width;
+    let cell_height = CHAR_WIDTH + PADDING * 2;
+    let title_height = cell_height;
+
+    let size = labels.len() as u32;
+    let width = label_width + cell_width * size.max(1);
+    let height = title_height + cell_height * (size + 1);
+    let mut canvas = Canvas::new(width, height);
+
+    canvas.draw_text(PADDING, PADDING, &fit_label(title, (width / CHAR_WIDTH) as usize - 1), TEXT);
+
+    // Header row with who the quotes were attributed to
+    canvas.draw_text(PADDING, title_height + PADDING, "said \\ as", MUTED_TEXT);
+    for (column, label) in labels.iter().enumerate() {
+        let x = label_width + column as u32 * cell_width;
+        canvas.draw_text(x + PADDING, title_height + PADDING, &fit_label(label, LABEL_CHARS), MUTED_TEXT);
+    }
+
+    let max_miss = counts.iter()
+        .enumerate()
+        .flat_map(|(row, cells)| cells.iter().enumerate().filter(move |(column, _)| *column != row).map(|(_, count)| *count))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let max_hit = counts.iter()
+        .enumerate()
+        .filter_map(|(row, cells)| cells.get(row).copied())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    for (row, label) in labels.iter().enumerate() {
+        let y = title_height + cell_height * (row as u32 + 1);
+        canvas.fill_rect(0, y, width, 1, GRID);
+        canvas.draw_text(PADDING, y + PADDING, &fit_label(label, LABEL_CHARS), TEXT);
+
+        for column in 0..labels.len() {
+            let count = counts.get(row).and_then(|cells| cells.get(column)).copied().unwrap_or(0);
+            let x = label_width + column as u32 * cell_width;
+            let color = if row == column {
+                shade(HIT, count as f64 / max_hit as f64)
+            } else {
+                shade(MISS, count as f64 / max_miss as f64)
+            };
+            canvas.fill_rect(x + 1, y + 1, cell_width - 1, cell_height - 1, color);
+            if count > 0 {
+                canvas.draw_text(x + PADDING, y + PADDING, &count.to_string(), TEXT);
+            }
+        }
+    }
+
+    canvas.encode_png()
+}
+
+/// Renders a series as a one-line text chart, e.g. for points over time in an embed.
+pub fn sparkline(values: &[i64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let (Some(min), Some(max)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    let range = (max - min).max(1) as f64;
+
+    values.iter()
+        .map(|value| {
+            let level = ((value - min) as f64 / range * (BARS.len() - 1) as f64).round() as usize;
+            BARS[level.min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_png() {
+        let labels = vec!["Flixis".to_string(), "Someone with a long name".to_string()];
+        let counts = vec![vec![4, 1], vec![2, 0]];
+        let png = render_confusion_table("Confusion", &labels, &counts).unwrap();
+        assert!(png.starts_with(&[0x89, b'P', b'N', b'G']));
+    }
+
+    #[test]
+    fn sparkline_scales_between_min_and_max() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[0, 35, 70]), "▁▅█");
+        assert_eq!(sparkline(&[-10, -10]), "▁▁");
+    }
+
+    #[test]
+    fn long_labels_are_shortened() {
+        assert_eq!(fit_label("Flixis", 10), "Flixis");
+        assert_eq!(fit_label("Someone with a long name", 10), "Someone w.");
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use sqlx::MySqlPool;
+
+/// How many wrong quotes are shown next to the real one.
+pub const DECOYS: usize = 3;
+/// Labels of the quotes on the round message and its buttons.
+pub const LABELS: [&str; DECOYS + 1] = ["A", "B", "C", "D"];
+/// Decoys are said within this many days of the real quote.
+const ERA_DAYS: i64 = 180;
+/// Longest a quote is shown, four long ones would not fit in a message.
+const PREVIEW_CHARS: usize = 300;
+
+/// One of the quotes players pick from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteOption {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub content: String,
+}
+
+/// Decoys are between half and double the length of the real quote.
+fn length_bounds(length: usize) -> (i64, i64) {
+    ((length / 2).max(1) as i64, (length * 2) as i64)
+}
+
+/// Loads quotes by other allowed users that look like the real one: of a similar
+/// length and said around the same time.
+pub async fn fetch_decoys(
+    db_pool: &MySqlPool,
+    guild_id: i64,
+    real: &QuoteOption,
+    said_at: DateTime<Utc>,
+    allowed_users: &[i64],
+) -> Result<Vec<QuoteOption>, sqlx::Error> {
+    let (min_length, max_length) = length_bounds(real.content.chars().count());
+    let era = TimeDelta::days(ERA_DAYS);
+
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "SELECT Id, UserId, Name, Content FROM wdl_database.discord_messages WHERE GuildId = ",
+    );
+    query_builder.push_bind(guild_id);
+    query_builder.push(" AND UserId <> ");
+    query_builder.push_bind(real.user_id);
+    query_builder.push(" AND CHAR_LENGTH(Content) BETWEEN ");
+    query_builder.push_bind(min_length);
+    query_builder.push(" AND ");
+    query_builder.push_bind(max_length);
+    query_builder.push(" AND Timestamp BETWEEN ");
+    query_builder.push_bind(said_at - era);
+    query_builder.push(" AND ");
+    query_builder.push_bind(said_at + era);
+    if !allowed_users.is_empty() {
+        query_builder.push(" AND UserId IN (");
+        let mut separated = query_builder.separated(", ");
+        for &id in allowed_users.iter() {
+            separated.push_bind(id);
+        }
+        separated.push_unseparated(")");
+    }
+    query_builder.push(" ORDER BY RAND() LIMIT 40");
+
+    let rows = query_builder.build_query_as::<(i64, i64, String, String)>().fetch_all(db_pool).await?;
+    let candidates = rows.into_iter()
+        .map(|(id, user_id, name, content)| QuoteOption { id, user_id, name, content })
+        .collect();
+    Ok(pick_decoys(candidates, &mut rand::rng()))
+}
+
+/// Picks the decoys from the candidates, preferring quotes by different people
+/// so the real author's style stands out against several others.
+fn pick_decoys(mut candidates: Vec<QuoteOption>, rng: &mut impl Rng) -> Vec<QuoteOption> {
+    candidates.shuffle(rng);
+
+    let mut decoys: Vec<QuoteOption> = Vec::with_capacity(DECOYS);
+    let mut rest = Vec::new();
+    for candidate in candidates {
+        if decoys.iter().any(|decoy| decoy.user_id == candidate.user_id) {
+            rest.push(candidate);
+        } else if decoys.len() < DECOYS {
+            decoys.push(candidate);
+        }
+    }
+    let missing = DECOYS.saturating_sub(decoys.len());
+    decoys.extend(rest.into_iter().take(missing));
+    decoys
+}
+
+/// Mixes the real quote in with the decoys, in a random order.
+pub fn arrange(real: QuoteOption, decoys: Vec<QuoteOption>, rng: &mut impl Rng) -> Vec<QuoteOption> {
+    let mut options = decoys;
+    options.push(real);
+    options.shuffle(rng);
+    options
+}
+
+/// A quote shortened to fit next to three others.
+pub fn preview(content: &str) -> String {
+    if content.chars().count() <= PREVIEW_CHARS {
+        return content.to_string();
+    }
+    let shortened: String = content.chars().take(PREVIEW_CHARS).collect();
+    format!("{}…", shortened.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn option(id: i64, user_id: i64) -> QuoteOption {
+        QuoteOption { id, user_id, name: format!("user {}", user_id), content: format!("quote {}", id) }
+    }
+
+    #[test]
+    fn decoys_prefer_different_authors() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let candidates = vec![option(1, 10), option(2, 10), option(3, 10), option(4, 20), option(5, 30)];
+        let decoys = pick_decoys(candidates, &mut rng);
+        let mut authors: Vec<i64> = decoys.iter().map(|decoy| decoy.user_id).collect();
+        authors.sort_unstable();
+        assert_eq!(authors, vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn decoys_repeat_authors_when_needed() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let decoys = pick_decoys(vec![option(1, 10), option(2, 10), option(3, 20)], &mut rng);
+        assert_eq!(decoys.len(), DECOYS);
+        assert_eq!(pick_decoys(vec![option(1, 10)], &mut rng).len(), 1);
+    }
+
+    #[test]
+    fn arrange_keeps_every_quote() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let options = arrange(option(9, 99), vec![option(1, 10), option(2, 20), option(3, 30)], &mut rng);
+        assert_eq!(options.len(), LABELS.len());
+        assert!(options.contains(&option(9, 99)));
+    }
+
+    #[test]
+    fn long_quotes_are_shortened() {
+        assert_eq!(preview("short"), "short");
+        let long = "word ".repeat(100);
+        let shortened = preview(&long);
+        assert!(shortened.ends_with('…'));
+        assert_eq!(shortened.chars().count(), PREVIEW_CHARS);
+        assert_eq!(length_bounds(40), (20, 80));
+    }
+}
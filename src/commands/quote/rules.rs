@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use serenity::all::{CommandOptionType, CreateCommandOption, ResolvedOption, ResolvedValue};
+use std::time::Duration;
+
+/// Scoring and timing rules for a guessquote round, read from the `[guessquote]`
+/// section of `config/quote_settings.toml`. Missing keys fall back to the defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RoundRules {
+    /// How long players have to answer, in seconds.
+    pub duration_secs: u64,
+    /// Minimum number of characters a message needs to be picked as a quote.
+    pub min_length: u32,
+    /// Points for an instant correct answer.
+    pub max_points: i32,
+    /// Points for a correct answer right before the timer runs out.
+    pub min_points: i32,
+    /// Points subtracted for a wrong guess.
+    pub wrong_guess_penalty: i32,
+    /// Points subtracted for a wrong guess in hardcore rounds.
+    pub hardcore_wrong_guess_penalty: i32,
+    /// Bonus points per level of the player's current streak.
+    pub streak_bonus_per_level: i32,
+    /// Upper limit for the streak bonus.
+    pub streak_bonus_cap: i32,
+    /// Hardcore rounds allow a single guess per player and use the harsher penalty.
+    pub hardcore: bool,
+    /// How much each hint lowers the maximum points of a round.
+    pub hint_cost: i32,
+    /// Largest share of their points, in percent, players may wager on an answer. 0 turns wagers off.
+    pub max_wager_percent: u32,
+}
+
+impl Default for RoundRules {
+    fn default() -> Self {
+        RoundRules {
+            duration_secs: 30,
+            min_length: 20,
+            max_points: 100,
+            min_points: 10,
+            wrong_guess_penalty: 5,
+            hardcore_wrong_guess_penalty: 20,
+            streak_bonus_per_level: 5,
+            streak_bonus_cap: 25,
+            hardcore: false,
+            hint_cost: 20,
+            max_wager_percent: 50,
+        }
+    }
+}
+
+impl RoundRules {
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.duration_secs)
+    }
+
+    /// The penalty applied to a wrong guess under these rules.
+    pub fn penalty(&self) -> i32 {
+        if self.hardcore {
+            self.hardcore_wrong_guess_penalty
+        } else {
+            self.wrong_guess_penalty
+        }
+    }
+
+    /// Applies the per-round overrides a player passed to `/guessquote start`.
+    pub fn with_overrides(&self, options: &[ResolvedOption]) -> RoundRules {
+        let mut rules = self.clone();
+        for option in options {
+            match (option.name, &option.value) {
+                ("duration", ResolvedValue::Integer(secs)) => rules.duration_secs = *secs as u64,
+                ("min_length", ResolvedValue::Integer(length)) => rules.min_length = *length as u32,
+                ("hardcore", ResolvedValue::Boolean(hardcore)) => rules.hardcore = *hardcore,
+                _ => {}
+            }
+        }
+        rules
+    }
+
+    /// One-line summary of the active rules for the round announcement.
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "⏱️ {}s · 📏 quotes of {}+ characters · ✅ {}–{} points (faster is more) · ❌ -{} per wrong guess · 🔥 +{} per streak level (max +{})",
+            self.duration_secs,
+            self.min_length,
+            self.min_points,
+            self.max_points,
+            self.penalty(),
+            self.streak_bonus_per_level,
+            self.streak_bonus_cap
+        );
+        if self.hardcore {
+            description.push_str(" · 💀 hardcore: one guess each");
+        }
+        description
+    }
+}
+
+/// The slash-command options that override the configured rules.
+pub fn options() -> Vec<CreateCommandOption> {
+    vec![
+        CreateCommandOption::new(CommandOptionType::Integer, "duration", "Seconds to answer")
+            .min_int_value(10)
+            .max_int_value(300),
+        CreateCommandOption::new(CommandOptionType::Integer, "min_length", "Minimum quote length in characters")
+            .min_int_value(1)
+            .max_int_value(500),
+        CreateCommandOption::new(CommandOptionType::Boolean, "hardcore", "One guess each and a bigger penalty for wrong guesses"),
+    ]
+}
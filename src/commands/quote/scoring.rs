@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use super::RoundRules;
+
+/// Points awarded for a single guess, split into its parts for the round summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuessPoints {
+    pub base: i32,
+    pub streak_bonus: i32,
+}
+
+impl GuessPoints {
+    pub fn total(&self) -> i32 {
+        self.base + self.streak_bonus
+    }
+}
+
+/// Points for a correct answer given after `elapsed`. Scales linearly from
+/// `max_points` for an instant answer down to `min_points` when the timer runs out.
+pub fn time_points(elapsed: Duration, rules: &RoundRules) -> i32 {
+    let duration_secs = rules.duration_secs as f64;
+    if duration_secs <= 0.0 {
+        return rules.min_points;
+    }
+
+    let remaining_fraction = ((duration_secs - elapsed.as_secs_f64()) / duration_secs).clamp(0.0, 1.0);
+    let points = remaining_fraction * (rules.max_points - rules.min_points) as f64 + rules.min_points as f64;
+    (points as i32).max(rules.min_points)
+}
+
+/// Bonus for a player's current streak, capped by the rules.
+pub fn streak_bonus(current_streak: i32, rules: &RoundRules) -> i32 {
+    (current_streak.max(0) * rules.streak_bonus_per_level).min(rules.streak_bonus_cap)
+}
+
+/// Scores one guess. The time points of a correct guess are scaled by the quote's
+/// difficulty multiplier. Wrong guesses cost the penalty and never earn a streak bonus.
+pub fn score_guess(is_correct: bool, elapsed: Duration, current_streak: i32, multiplier: f64, rules: &RoundRules) -> GuessPoints {
+    if is_correct {
+        GuessPoints {
+            base: (time_points(elapsed, rules) as f64 * multiplier).round() as i32,
+            streak_bonus: streak_bonus(current_streak, rules),
+        }
+    } else {
+        GuessPoints {
+            base: -rules.penalty(),
+            streak_bonus: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instant_answer_gets_max_points() {
+        let rules = RoundRules::default();
+        assert_eq!(time_points(Duration::ZERO, &rules), 100);
+    }
+
+    #[test]
+    fn points_drop_with_response_time() {
+        let rules = RoundRules::default();
+        assert_eq!(time_points(Duration::from_secs(15), &rules), 55);
+        assert!(time_points(Duration::from_secs(3), &rules) > time_points(Duration::from_secs(20), &rules));
+    }
+
+    #[test]
+    fn late_answer_gets_min_points() {
+        let rules = RoundRules::default();
+        assert_eq!(time_points(Duration::from_secs(30), &rules), 10);
+        assert_eq!(time_points(Duration::from_secs(45), &rules), 10);
+    }
+
+    #[test]
+    fn time_curve_follows_round_duration() {
+        let rules = RoundRules { duration_secs: 60, ..RoundRules::default() };
+        assert_eq!(time_points(Duration::from_secs(30), &rules), 55);
+    }
+
+    #[test]
+    fn streak_bonus_is_capped() {
+        let rules = RoundRules::default();
+        assert_eq!(streak_bonus(0, &rules), 0);
+        assert_eq!(streak_bonus(2, &rules), 10);
+        assert_eq!(streak_bonus(5, &rules), 25);
+        assert_eq!(streak_bonus(12, &rules), 25);
+    }
+
+    #[test]
+    fn wrong_guess_costs_penalty_without_bonus() {
+        let rules = RoundRules::default();
+        let points = score_guess(false, Duration::from_secs(1), 4, 1.5, &rules);
+        assert_eq!(points, GuessPoints { base: -5, streak_bonus: 0 });
+
+        let hardcore = RoundRules { hardcore: true, ..RoundRules::default() };
+        assert_eq!(score_guess(false, Duration::from_secs(1), 4, 1.5, &hardcore).total(), -20);
+    }
+
+    #[test]
+    fn correct_guess_adds_streak_bonus() {
+        let rules = RoundRules::default();
+        let points = score_guess(true, Duration::ZERO, 3, 1.0, &rules);
+        assert_eq!(points, GuessPoints { base: 100, streak_bonus: 15 });
+        assert_eq!(points.total(), 115);
+    }
+
+    #[test]
+    fn difficulty_scales_time_points_only() {
+        let rules = RoundRules::default();
+        let points = score_guess(true, Duration::ZERO, 3, 1.5, &rules);
+        assert_eq!(points, GuessPoints { base: 150, streak_bonus: 15 });
+    }
+}
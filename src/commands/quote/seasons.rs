@@ -0,0 +1,207 @@
+use log::{info, warn};
+use serenity::all::{
+    CommandInteraction, CommandOptionType, CreateCommand, CreateCommandOption,
+    CreateInteractionResponse, CreateInteractionResponseMessage, GuildId, Permissions, ResolvedOption,
+    ResolvedValue,
+};
+use sqlx::{Executor, MySql, MySqlPool};
+
+use super::fetch_latest_names;
+
+pub fn register() -> CreateCommand {
+    let close_option = CreateCommandOption::new(CommandOptionType::SubCommand, "close", "Close the current season and start the next one")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::String, "next_name", "Name of the next season").max_length(100));
+
+    CreateCommand::new("season")
+        .description("Manage guessquote seasons")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .add_option(close_option)
+}
+
+/// The season currently being played in the guild, as `(id, name)`. A guild
+/// playing for the first time gets its "Season 1" opened here, the unique key on
+/// open seasons turns a second concurrent insert into a no-op.
+pub async fn current_season(db_pool: &MySqlPool, guild_id: GuildId) -> Option<(i32, String)> {
+    if let Err(e) = sqlx::query(
+        "INSERT IGNORE INTO wdl_database.quote_seasons (guild_id, name)
+         SELECT ?, 'Season 1' FROM DUAL
+         WHERE NOT EXISTS (SELECT 1 FROM wdl_database.quote_seasons WHERE guild_id = ?)",
+    )
+        .bind(i64::from(guild_id))
+        .bind(i64::from(guild_id))
+        .execute(db_pool)
+        .await
+    {
+        warn!("Failed to open the first season for guild {}: {}", guild_id, e);
+    }
+
+    match sqlx::query_as::<_, (i32, String)>(
+        "SELECT id, name FROM wdl_database.quote_seasons WHERE guild_id = ? AND ended_at IS NULL ORDER BY id DESC LIMIT 1",
+    )
+        .bind(i64::from(guild_id))
+        .fetch_optional(db_pool)
+        .await
+    {
+        Ok(season) => season,
+        Err(e) => {
+            warn!("Failed to fetch current season: {}", e);
+            None
+        }
+    }
+}
+
+/// The guild's season with this number, as `(id, name)`. Seasons are numbered
+/// per guild from 1, in the order they were opened.
+pub async fn find_season(db_pool: &MySqlPool, guild_id: GuildId, number: i64) -> Result<Option<(i32, String)>, sqlx::Error> {
+    if number < 1 {
+        return Ok(None);
+    }
+    sqlx::query_as::<_, (i32, String)>(
+        "SELECT id, name FROM wdl_database.quote_seasons WHERE guild_id = ? ORDER BY id LIMIT 1 OFFSET ?",
+    )
+        .bind(i64::from(guild_id))
+        .bind(number - 1)
+        .fetch_optional(db_pool)
+        .await
+}
+
+/// The number of a season within its guild, the inverse of `find_season`.
+pub async fn season_number<'e>(
+    executor: impl Executor<'e, Database = MySql>,
+    guild_id: GuildId,
+    season_id: i32,
+) -> Result<i64, sqlx::Error> {
+    let (number,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM wdl_database.quote_seasons WHERE guild_id = ? AND id <= ?")
+        .bind(i64::from(guild_id))
+        .bind(season_id)
+        .fetch_one(executor)
+        .await?;
+    Ok(number)
+}
+
+// Command handler for the season command and its subcommands
+pub async fn handle_commands(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let options = command.data.options();
+    let response = match options.first() {
+        Some(ResolvedOption { name: "close", value: ResolvedValue::SubCommand(options), .. }) => {
+            let next_name = options.iter().find_map(|option| match option.value {
+                ResolvedValue::String(name) if option.name == "next_name" => Some(name.trim().to_string()),
+                _ => None,
+            });
+            let Some(guild_id) = command.guild_id else {
+                return Err("season command used outside of a guild".into());
+            };
+            match close_season(db_pool, guild_id, i64::from(command.user.id), next_name).await {
+                Ok(announcement) => CreateInteractionResponseMessage::new().content(announcement),
+                Err(e) => {
+                    warn!("Failed to close season: {}", e);
+                    CreateInteractionResponseMessage::new()
+                        .content("Sorry, I couldn't close the season right now.")
+                        .ephemeral(true)
+                }
+            }
+        }
+        _ => CreateInteractionResponseMessage::new().content("Unknown subcommand.").ephemeral(true),
+    };
+
+    command
+        .create_response(&ctx.http, CreateInteractionResponse::Message(response))
+        .await?;
+    Ok(())
+}
+
+/// Archives the final standings of the current season, crowns its champion and
+/// opens the next season. Returns the announcement for the channel.
+async fn close_season(db_pool: &MySqlPool, guild_id: GuildId, closed_by: i64, next_name: Option<String>) -> Result<String, sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+
+    let Some((season_id, season_name)) = sqlx::query_as::<_, (i32, String)>(
+        "SELECT id, name FROM wdl_database.quote_seasons WHERE guild_id = ? AND ended_at IS NULL ORDER BY id DESC LIMIT 1 FOR UPDATE",
+    )
+        .bind(i64::from(guild_id))
+        .fetch_optional(&mut *transaction)
+        .await?
+    else {
+        return Ok("There is no open season to close.".to_string());
+    };
+
+    sqlx::query(
+        "UPDATE wdl_database.quote_season_scores s
+         JOIN (
+             SELECT user_id, ROW_NUMBER() OVER (ORDER BY points DESC, correct_guesses DESC) as season_rank
+             FROM wdl_database.quote_season_scores
+             WHERE season_id = ?
+         ) ranked ON ranked.user_id = s.user_id
+         SET s.final_rank = ranked.season_rank
+         WHERE s.season_id = ?",
+    )
+        .bind(season_id)
+        .bind(season_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    let podium = sqlx::query_as::<_, (i64, i32, i32)>(
+        "SELECT user_id, points, final_rank FROM wdl_database.quote_season_scores
+         WHERE season_id = ? AND final_rank <= 3
+         ORDER BY final_rank",
+    )
+        .bind(season_id)
+        .fetch_all(&mut *transaction)
+        .await?;
+    let champion_id = podium.first().map(|(user_id, _, _)| *user_id);
+
+    sqlx::query(
+        "UPDATE wdl_database.quote_seasons SET ended_at = CURRENT_TIMESTAMP, champion_id = ?, closed_by = ? WHERE id = ?",
+    )
+        .bind(champion_id)
+        .bind(closed_by)
+        .bind(season_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    // Seasons are numbered per guild, so count this guild's seasons instead of using the id
+    let season_number = season_number(&mut *transaction, guild_id, season_id).await?;
+    let next_name = next_name
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("Season {}", season_number + 1));
+    let next_id = sqlx::query("INSERT INTO wdl_database.quote_seasons (guild_id, name) VALUES (?, ?)")
+        .bind(i64::from(guild_id))
+        .bind(&next_name)
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_id();
+
+    transaction.commit().await?;
+    info!("Closed season {} ({}) in guild {}, champion: {:?}, next season: {}", season_id, season_name, guild_id, champion_id, next_id);
+
+    let user_ids: Vec<i64> = podium.iter().map(|(user_id, _, _)| *user_id).collect();
+    let names = fetch_latest_names(db_pool, &user_ids).await;
+
+    let mut announcement = format!("🏁 **{} is over!**\n\n", season_name);
+    match champion_id {
+        Some(champion_id) => {
+            announcement.push_str(&format!("👑 Congratulations <@{}>, champion of {}!\n\n", champion_id, season_name));
+            for (user_id, points, rank) in podium.iter() {
+                let medal = match rank {
+                    1 => "🥇",
+                    2 => "🥈",
+                    _ => "🥉",
+                };
+                let name = names.get(user_id).cloned().unwrap_or_else(|| user_id.to_string());
+                announcement.push_str(&format!("{} {} - {} points\n", medal, name, points));
+            }
+        }
+        None => announcement.push_str("Nobody scored any points this season.\n"),
+    }
+    announcement.push_str(&format!(
+        "\n**{}** starts now, everyone is back at zero! Final standings: `/scoreboard season:{}`",
+        next_name, season_number
+    ));
+
+    Ok(announcement)
+}
@@ -0,0 +1,231 @@
+use log::{info, warn};
+use rand::seq::IndexedRandom;
+use serde::Deserialize;
+use serenity::all::{ChannelId, GuildId};
+use sqlx::MySqlPool;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use super::allowed_users;
+use super::difficulty::{self, Difficulty};
+use crate::QUOTE_SELECTION;
+
+/// How quotes are picked, read from the `[selection]` section of the quote settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SelectionSettings {
+    /// Seconds between reloads of the cached quote ids.
+    pub refresh_secs: u64,
+    /// How many of a channel's latest quotes are skipped when picking the next one.
+    pub recent_window: usize,
+}
+
+impl Default for SelectionSettings {
+    fn default() -> Self {
+        SelectionSettings {
+            refresh_secs: 600,
+            recent_window: 100,
+        }
+    }
+}
+
+/// What a round asks of its quote.
+pub struct QuoteFilter {
+    pub min_length: u32,
+    pub difficulty: Option<Difficulty>,
+    /// Authors whose quotes must not be picked, like the players of a duel.
+    pub excluded_authors: Vec<i64>,
+}
+
+/// The columns of an eligible quote needed to pick one without touching the table.
+#[derive(Debug, Clone, Copy)]
+struct PoolEntry {
+    id: i64,
+    user_id: i64,
+    length: u32,
+    difficulty: f64,
+}
+
+struct GuildPool {
+    entries: Vec<PoolEntry>,
+    loaded_at: Instant,
+}
+
+#[derive(Default)]
+struct PoolState {
+    guilds: HashMap<GuildId, GuildPool>,
+    recent: HashMap<ChannelId, VecDeque<i64>>,
+}
+
+/// Picks random quotes from an in-memory pool of eligible ids per guild, so a
+/// pick doesn't scan `discord_messages`, and skips the quotes a channel saw last.
+#[derive(Clone, Default)]
+pub struct QuotePool {
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl QuotePool {
+    pub fn new() -> Self {
+        QuotePool::default()
+    }
+
+    fn settings() -> SelectionSettings {
+        QUOTE_SELECTION.get().cloned().unwrap_or_default()
+    }
+
+    /// Picks a quote id for the channel and remembers it as recently used.
+    /// Returns `None` when the guild has no quote matching the filter.
+    pub async fn pick(
+        &self,
+        db_pool: &MySqlPool,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        filter: &QuoteFilter,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let settings = Self::settings();
+        let stale = {
+            let state = self.state.lock().await;
+            state.guilds.get(&guild_id)
+                .is_none_or(|pool| pool.loaded_at.elapsed() >= Duration::from_secs(settings.refresh_secs))
+        };
+        if stale {
+            self.load_guild(db_pool, guild_id).await?;
+        }
+
+        let mut state = self.state.lock().await;
+        let PoolState { guilds, recent } = &mut *state;
+        let entries = guilds.get(&guild_id).map(|pool| pool.entries.as_slice()).unwrap_or(&[]);
+        let recent = recent.entry(channel_id).or_default();
+
+        let picked = choose(entries, recent, filter);
+        if let Some(id) = picked {
+            recent.push_back(id);
+            while recent.len() > settings.recent_window {
+                recent.pop_front();
+            }
+        }
+        Ok(picked)
+    }
+
+    /// Reloads the eligible quote ids of a guild.
+    async fn load_guild(&self, db_pool: &MySqlPool, guild_id: GuildId) -> Result<(), sqlx::Error> {
+        let allowed_users = allowed_users(guild_id);
+
+        let mut query_builder = sqlx::QueryBuilder::new("SELECT Id, UserId, CHAR_LENGTH(Content), ");
+        query_builder.push(difficulty::DIFFICULTY_SQL);
+        query_builder.push(" FROM wdl_database.discord_messages WHERE GuildId = ");
+        query_builder.push_bind(i64::from(guild_id));
+        if !allowed_users.is_empty() {
+            query_builder.push(" AND UserId IN (");
+            let mut separated = query_builder.separated(", ");
+            for &id in allowed_users.iter() {
+                separated.push_bind(id);
+            }
+            separated.push_unseparated(")");
+        }
+
+        let entries: Vec<PoolEntry> = query_builder.build_query_as::<(i64, i64, i64, f64)>()
+            .fetch_all(db_pool)
+            .await?
+            .into_iter()
+            .map(|(id, user_id, length, difficulty)| PoolEntry { id, user_id, length: length as u32, difficulty })
+            .collect();
+
+        info!("Loaded {} quotes into the pool of guild {}", entries.len(), guild_id);
+        self.state.lock().await.guilds.insert(guild_id, GuildPool { entries, loaded_at: Instant::now() });
+        Ok(())
+    }
+
+    /// Reloads every cached guild on the configured interval, so quotes scraped
+    /// since and changed difficulties are picked up.
+    pub fn spawn_refresh(&self, db_pool: MySqlPool) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(Self::settings().refresh_secs.max(1)));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let guild_ids: Vec<GuildId> = pool.state.lock().await.guilds.keys().copied().collect();
+                for guild_id in guild_ids {
+                    if let Err(e) = pool.load_guild(&db_pool, guild_id).await {
+                        warn!("Failed to refresh the quote pool of guild {}: {}", guild_id, e);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Picks a random entry matching the filter. Recently used quotes are skipped
+/// unless nothing else is left. A requested difficulty band is required, so
+/// there is no pick when the band is empty. Excluded authors are never picked.
+fn choose(entries: &[PoolEntry], recent: &VecDeque<i64>, filter: &QuoteFilter) -> Option<i64> {
+    let recent: HashSet<i64> = recent.iter().copied().collect();
+    let eligible: Vec<&PoolEntry> = entries.iter()
+        .filter(|entry| entry.length >= filter.min_length && !filter.excluded_authors.contains(&entry.user_id))
+        .filter(|entry| filter.difficulty.is_none_or(|band| Difficulty::of(entry.difficulty) == band))
+        .collect();
+
+    let fresh: Vec<&PoolEntry> = eligible.iter().copied().filter(|entry| !recent.contains(&entry.id)).collect();
+    let candidates = if fresh.is_empty() { eligible } else { fresh };
+
+    candidates.choose(&mut rand::rng()).map(|entry| entry.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64, length: u32, difficulty: f64) -> PoolEntry {
+        PoolEntry { id, user_id: id * 10, length, difficulty }
+    }
+
+    fn filter(min_length: u32, difficulty: Option<Difficulty>) -> QuoteFilter {
+        QuoteFilter { min_length, difficulty, excluded_authors: Vec::new() }
+    }
+
+    #[test]
+    fn skips_short_and_recent_quotes() {
+        let entries = [entry(1, 50, 0.5), entry(2, 5, 0.5), entry(3, 50, 0.5)];
+        let recent = VecDeque::from([1]);
+        for _ in 0..20 {
+            assert_eq!(choose(&entries, &recent, &filter(20, None)), Some(3));
+        }
+    }
+
+    #[test]
+    fn repeats_when_every_quote_was_recent() {
+        let entries = [entry(1, 50, 0.5)];
+        let recent = VecDeque::from([1]);
+        assert_eq!(choose(&entries, &recent, &filter(20, None)), Some(1));
+        assert_eq!(choose(&entries, &recent, &filter(100, None)), None);
+    }
+
+    #[test]
+    fn keeps_to_the_requested_band() {
+        let entries = [entry(1, 50, 0.2), entry(2, 50, 0.5), entry(3, 50, 0.8)];
+        for _ in 0..20 {
+            assert_eq!(choose(&entries, &VecDeque::from([3]), &filter(1, Some(Difficulty::Hard))), Some(3));
+        }
+    }
+
+    #[test]
+    fn an_empty_band_has_no_pick() {
+        let without_hard = [entry(1, 50, 0.2), entry(2, 50, 0.5)];
+        assert_eq!(choose(&without_hard, &VecDeque::new(), &filter(1, Some(Difficulty::Hard))), None);
+        assert!(choose(&without_hard, &VecDeque::new(), &filter(1, None)).is_some());
+    }
+
+    #[test]
+    fn never_picks_excluded_authors() {
+        let entries = [entry(1, 50, 0.5), entry(2, 50, 0.5)];
+        let filter = QuoteFilter { excluded_authors: vec![10], ..filter(1, None) };
+        for _ in 0..20 {
+            assert_eq!(choose(&entries, &VecDeque::from([2]), &filter), Some(2));
+        }
+        let filter = QuoteFilter { excluded_authors: vec![10, 20], ..filter };
+        assert_eq!(choose(&entries, &VecDeque::new(), &filter), None);
+    }
+}
@@ -0,0 +1,371 @@
+use log::{info, warn};
+use serenity::all::{
+    CommandInteraction, CommandOptionType, CreateAttachment, CreateCommand, CreateCommandOption,
+    CreateEmbed, CreateEmbedFooter, CreateInteractionResponseFollowup, ResolvedOption,
+    ResolvedValue,
+};
+use serenity::model::Timestamp;
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+
+use super::{fetch_latest_names, render};
+
+/// Authors shown in the confusion table image.
+const TABLE_SIZE: usize = 8;
+/// Guesses needed about an author before they are ranked on recognisability.
+const MIN_GUESSES_FOR_RANKING: i64 = 5;
+/// Guesses a player needs about an author before it can count as a blind spot.
+const MIN_GUESSES_FOR_BLIND_SPOT: i64 = 3;
+
+pub fn register() -> CreateCommand {
+    let confusion_option = CreateCommandOption::new(CommandOptionType::SubCommand, "confusion", "Show who gets mistaken for whom")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "Also show this player's blind spots"));
+
+    let profile_option = CreateCommandOption::new(CommandOptionType::SubCommand, "profile", "Show a player's guessquote profile")
+        .add_sub_option(CreateCommandOption::new(CommandOptionType::User, "user", "The player to show, defaults to you"));
+
+    CreateCommand::new("quotestats")
+        .description("Statistics about the guessquote game")
+        .dm_permission(false)
+        .add_option(confusion_option)
+        .add_option(profile_option)
+}
+
+// Command handler for the quotestats command and its subcommands
+pub async fn handle_commands(
+    ctx: serenity::client::Context,
+    command: &CommandInteraction,
+    db_pool: &MySqlPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = command.guild_id.map(i64::from) else {
+        return super::reply_ephemeral(&ctx, command, "Guessquote statistics are only available in servers.").await;
+    };
+
+    // Defer the response to buy time for the queries and rendering
+    command.defer(&ctx.http).await?;
+
+    let options = command.data.options();
+    let result = match options.first() {
+        Some(ResolvedOption { name: "confusion", value: ResolvedValue::SubCommand(options), .. }) => {
+            show_confusion(guild_id, options, db_pool).await
+        }
+        Some(ResolvedOption { name: "profile", value: ResolvedValue::SubCommand(options), .. }) => {
+            let user_id = user_option(options, "user").unwrap_or_else(|| i64::from(command.user.id));
+            show_profile(guild_id, user_id, db_pool).await
+        }
+        _ => Ok(CreateInteractionResponseFollowup::new().content("Unknown subcommand.")),
+    };
+
+    // The deferred response keeps showing "thinking" until a followup arrives,
+    // so failures get one too
+    let followup = match result {
+        Ok(followup) => followup,
+        Err(e) => {
+            warn!("Failed to fetch guessquote statistics: {}", e);
+            CreateInteractionResponseFollowup::new().content("Sorry, I couldn't fetch the statistics right now.")
+        }
+    };
+
+    command.create_followup(&ctx.http, followup).await?;
+    Ok(())
+}
+
+/// Reads an optional user option as a raw id.
+fn user_option(options: &[ResolvedOption<'_>], name: &str) -> Option<i64> {
+    options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) if option.name == name => Some(i64::from(user.id)),
+        _ => None,
+    })
+}
+
+fn name_of(names: &HashMap<i64, String>, user_id: i64) -> String {
+    names.get(&user_id).cloned().unwrap_or_else(|| user_id.to_string())
+}
+
+async fn show_confusion(
+    guild_id: i64,
+    options: &[ResolvedOption<'_>],
+    db_pool: &MySqlPool,
+) -> Result<CreateInteractionResponseFollowup, sqlx::Error> {
+    // How often each author's quotes were attributed to each candidate
+    let cells = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT r.quoted_user_id, g.guessed_user_id, COUNT(*)
+         FROM wdl_database.quote_guesses g
+         JOIN wdl_database.quote_rounds r ON r.id = g.round_id
+         WHERE r.guild_id = ? AND r.mode IN ('text', 'buttons') AND r.reverted_at IS NULL AND g.guessed_user_id IS NOT NULL
+         GROUP BY r.quoted_user_id, g.guessed_user_id",
+    )
+        .bind(guild_id)
+        .fetch_all(db_pool)
+        .await?;
+
+    // Correct answers per author, including guesses that named nobody
+    let recognition = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT r.quoted_user_id, CAST(SUM(g.correct) AS SIGNED), COUNT(*)
+         FROM wdl_database.quote_guesses g
+         JOIN wdl_database.quote_rounds r ON r.id = g.round_id
+         WHERE r.guild_id = ? AND r.mode IN ('text', 'buttons') AND r.reverted_at IS NULL
+         GROUP BY r.quoted_user_id",
+    )
+        .bind(guild_id)
+        .fetch_all(db_pool)
+        .await?;
+
+    if cells.is_empty() {
+        return Ok(CreateInteractionResponseFollowup::new()
+            .content("No guesses recorded yet! Start playing with /guessquote"));
+    }
+
+    let player_id = user_option(options, "user");
+    let blind_spots = match player_id {
+        Some(player_id) => {
+            sqlx::query_as::<_, (i64, i64, i64, Option<i64>)>(
+                "SELECT r.quoted_user_id,
+                        CAST(SUM(g.correct) AS SIGNED),
+                        COUNT(*),
+                        (SELECT g2.guessed_user_id
+                         FROM wdl_database.quote_guesses g2
+                         JOIN wdl_database.quote_rounds r2 ON r2.id = g2.round_id
+                         WHERE r2.guild_id = r.guild_id
+                           AND r2.mode IN ('text', 'buttons') AND r2.reverted_at IS NULL
+                           AND g2.guesser_id = g.guesser_id
+                           AND r2.quoted_user_id = r.quoted_user_id
+                           AND g2.correct = FALSE
+                           AND g2.guessed_user_id IS NOT NULL
+                         GROUP BY g2.guessed_user_id
+                         ORDER BY COUNT(*) DESC
+                         LIMIT 1) as mistaken_for
+                 FROM wdl_database.quote_guesses g
+                 JOIN wdl_database.quote_rounds r ON r.id = g.round_id
+                 WHERE r.guild_id = ? AND r.mode IN ('text', 'buttons') AND r.reverted_at IS NULL AND g.guesser_id = ?
+                 GROUP BY r.guild_id, g.guesser_id, r.quoted_user_id
+                 HAVING COUNT(*) >= ?
+                 ORDER BY SUM(g.correct) / COUNT(*) ASC, COUNT(*) DESC
+                 LIMIT 3",
+            )
+                .bind(guild_id)
+                .bind(player_id)
+                .bind(MIN_GUESSES_FOR_BLIND_SPOT)
+                .fetch_all(db_pool)
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    let mut user_ids: Vec<i64> = cells.iter().flat_map(|&(said, guessed, _)| [said, guessed]).collect();
+    user_ids.extend(blind_spots.iter().filter_map(|&(_, _, _, mistaken_for)| mistaken_for));
+    user_ids.extend(player_id);
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    let names = fetch_latest_names(db_pool, &user_ids).await;
+
+    // Mix-ups, biggest first
+    let mut mixups: Vec<&(i64, i64, i64)> = cells.iter().filter(|(said, guessed, _)| said != guessed).collect();
+    mixups.sort_by_key(|(_, _, count)| std::cmp::Reverse(*count));
+    let mixup_lines: Vec<String> = mixups.iter()
+        .take(5)
+        .map(|(said, guessed, count)| format!(
+            "**{}** mistaken for **{}** ({}×)",
+            name_of(&names, *said), name_of(&names, *guessed), count
+        ))
+        .collect();
+
+    // Recognisability, only for authors with enough guesses to be meaningful
+    let mut ranked: Vec<(i64, f64, i64)> = recognition.iter()
+        .filter(|(_, _, total)| *total >= MIN_GUESSES_FOR_RANKING)
+        .map(|&(user_id, correct, total)| (user_id, correct as f64 * 100.0 / total as f64, total))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let format_rank = |(user_id, accuracy, total): &(i64, f64, i64)| format!(
+        "**{}** — {}% of {} guesses", name_of(&names, *user_id), accuracy.round(), total
+    );
+    let most_recognisable: Vec<String> = ranked.iter().take(3).map(format_rank).collect();
+    let least_recognisable: Vec<String> = ranked.iter().rev().take(3).map(format_rank).collect();
+
+    let or_none = |lines: Vec<String>| if lines.is_empty() { "Not enough data yet".to_string() } else { lines.join("\n") };
+
+    let mut embed = CreateEmbed::new()
+        .title("🤔 Who gets mistaken for whom")
+        .color(0x5865F2)
+        .field("Most common mix-ups", or_none(mixup_lines), false)
+        .field("Most recognisable", or_none(most_recognisable), true)
+        .field("Least recognisable", or_none(least_recognisable), true)
+        .footer(CreateEmbedFooter::new(format!(
+            "Rankings need at least {} guesses per author", MIN_GUESSES_FOR_RANKING
+        )))
+        .timestamp(Timestamp::now());
+
+    if let Some(player_id) = player_id {
+        let lines: Vec<String> = blind_spots.iter()
+            .map(|(said, correct, total, mistaken_for)| {
+                let mut line = format!("**{}** — {}/{} right", name_of(&names, *said), correct, total);
+                if let Some(mistaken_for) = mistaken_for {
+                    line.push_str(&format!(", usually guessed as **{}**", name_of(&names, *mistaken_for)));
+                }
+                line
+            })
+            .collect();
+        embed = embed.field(format!("Blind spots for {}", name_of(&names, player_id)), or_none(lines), false);
+    }
+
+    // The table covers the authors with the most guesses about them
+    let mut totals: HashMap<i64, i64> = HashMap::new();
+    for &(said, _, count) in cells.iter() {
+        *totals.entry(said).or_default() += count;
+    }
+    let mut authors: Vec<(i64, i64)> = totals.into_iter().collect();
+    authors.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    let authors: Vec<i64> = authors.into_iter().take(TABLE_SIZE).map(|(user_id, _)| user_id).collect();
+
+    let counts_by_pair: HashMap<(i64, i64), i64> = cells.iter().map(|&(said, guessed, count)| ((said, guessed), count)).collect();
+    let counts: Vec<Vec<i64>> = authors.iter()
+        .map(|said| authors.iter().map(|guessed| counts_by_pair.get(&(*said, *guessed)).copied().unwrap_or(0)).collect())
+        .collect();
+    let labels: Vec<String> = authors.iter().map(|user_id| name_of(&names, *user_id)).collect();
+
+    let mut followup = CreateInteractionResponseFollowup::new();
+    match render::render_confusion_table("Quote said by (rows) vs guessed as (columns)", &labels, &counts) {
+        Ok(png) => {
+            embed = embed.image("attachment://confusion.png");
+            followup = followup.add_file(CreateAttachment::bytes(png, "confusion.png"));
+        }
+        Err(e) => warn!("Failed to render confusion table: {}", e),
+    }
+
+    info!("show_confusion: Built confusion stats from {} cells", cells.len());
+    Ok(followup.add_embed(embed))
+}
+
+async fn show_profile(guild_id: i64, user_id: i64, db_pool: &MySqlPool) -> Result<CreateInteractionResponseFollowup, sqlx::Error> {
+    let names = fetch_latest_names(db_pool, &[user_id]).await;
+    let name = name_of(&names, user_id);
+
+    let scores = sqlx::query_as::<_, (i32, i32, i32, i32, i32, i64)>(
+        "SELECT qs.correct_guesses, qs.total_attempts, qs.points, qs.current_streak, qs.best_streak,
+                (SELECT COUNT(*) + 1 FROM wdl_database.quote_scores better
+                 WHERE better.guild_id = qs.guild_id AND better.points > qs.points) as player_rank
+         FROM wdl_database.quote_scores qs
+         WHERE qs.guild_id = ? AND qs.user_id = ?",
+    )
+        .bind(guild_id)
+        .bind(user_id)
+        .fetch_optional(db_pool)
+        .await?;
+
+    let Some((correct, total, points, current_streak, best_streak, rank)) = scores else {
+        return Ok(CreateInteractionResponseFollowup::new()
+            .content(format!("{} hasn't played guessquote yet! Start playing with /guessquote", name)));
+    };
+    let accuracy = if total > 0 { correct as f64 * 100.0 / total as f64 } else { 0.0 };
+
+    // Points per week over the last couple of months, from every mode, wager, daily
+    // and correction in the ledger. Opening balances weren't earned in their week.
+    let weekly_points = sqlx::query_as::<_, (i64,)>(
+        "SELECT CAST(SUM(points) AS SIGNED)
+         FROM wdl_database.quote_points_ledger
+         WHERE guild_id = ? AND user_id = ? AND kind NOT IN ('opening', 'season_opening')
+           AND created_at >= NOW() - INTERVAL 8 WEEK
+         GROUP BY YEARWEEK(created_at, 3)
+         ORDER BY YEARWEEK(created_at, 3)",
+    )
+        .bind(guild_id)
+        .bind(user_id)
+        .fetch_all(db_pool)
+        .await?;
+
+    let fastest = sqlx::query_as::<_, (i32, String)>(
+        "SELECT g.latency_ms, m.Content
+         FROM wdl_database.quote_guesses g
+         JOIN wdl_database.quote_rounds r ON r.id = g.round_id
+         JOIN wdl_database.discord_messages m ON m.Id = g.message_id
+         WHERE r.guild_id = ? AND r.mode IN ('text', 'buttons') AND r.reverted_at IS NULL AND g.guesser_id = ? AND g.correct = TRUE
+         ORDER BY g.latency_ms ASC
+         LIMIT 1",
+    )
+        .bind(guild_id)
+        .bind(user_id)
+        .fetch_optional(db_pool)
+        .await?;
+
+    // How often this player's own quotes come up and get identified
+    let own_quotes = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT COUNT(DISTINCT r.id),
+                COUNT(g.id),
+                CAST(COALESCE(SUM(g.correct), 0) AS SIGNED)
+         FROM wdl_database.quote_rounds r
+         LEFT JOIN wdl_database.quote_guesses g ON g.round_id = r.id
+         WHERE r.guild_id = ? AND r.mode IN ('text', 'buttons') AND r.reverted_at IS NULL AND r.quoted_user_id = ? AND r.cancelled = FALSE",
+    )
+        .bind(guild_id)
+        .bind(user_id)
+        .fetch_one(db_pool)
+        .await?;
+
+    let duels = sqlx::query_as::<_, (f64, i32, i32, i32)>(
+        "SELECT rating, wins, losses, draws FROM wdl_database.quote_duel_ratings WHERE guild_id = ? AND user_id = ?",
+    )
+        .bind(guild_id)
+        .bind(user_id)
+        .fetch_optional(db_pool)
+        .await?;
+
+    let trend = if weekly_points.is_empty() {
+        "No games in the last 8 weeks".to_string()
+    } else {
+        let values: Vec<i64> = weekly_points.iter().map(|(points,)| *points).collect();
+        format!(
+            "`{}` {} points over {} active weeks",
+            render::sparkline(&values),
+            values.iter().sum::<i64>(),
+            values.len()
+        )
+    };
+
+    let fastest = match fastest {
+        Some((latency_ms, content)) => {
+            let preview: String = content.chars().take(80).collect();
+            format!("{:.2}s on _{}_", latency_ms as f64 / 1000.0, preview)
+        }
+        None => "No correct guesses recorded yet".to_string(),
+    };
+
+    let (rounds, guesses_about, identified) = own_quotes;
+    let own_quotes = if rounds == 0 {
+        "None of their quotes came up yet".to_string()
+    } else {
+        format!(
+            "Quoted in {} rounds, identified in {} of {} guesses ({}%)",
+            rounds,
+            identified,
+            guesses_about,
+            if guesses_about > 0 { (identified as f64 * 100.0 / guesses_about as f64).round() } else { 0.0 }
+        )
+    };
+
+    let duels = match duels {
+        Some((rating, wins, losses, draws)) => format!(
+            "⚔️ {}W {}L{} · rating {}",
+            wins,
+            losses,
+            if draws > 0 { format!(" {}D", draws) } else { String::new() },
+            rating.round()
+        ),
+        None => "No duels yet, challenge someone with /duel".to_string(),
+    };
+
+    let embed = CreateEmbed::new()
+        .title(format!("📇 Guessquote profile: {}", name))
+        .color(0x5865F2)
+        .field("Points", points.to_string(), true)
+        .field("Rank", format!("#{}", rank), true)
+        .field("Accuracy", format!("{}% ({}/{})", accuracy.round(), correct, total), true)
+        .field("Streak", format!("🔥 {} (Best: {})", current_streak, best_streak), true)
+        .field("Fastest correct guess", fastest, false)
+        .field("Points trend", trend, false)
+        .field("Their own quotes", own_quotes, false)
+        .field("Duels", duels, false)
+        .timestamp(Timestamp::now());
+
+    info!("show_profile: Built profile for user {}", user_id);
+    Ok(CreateInteractionResponseFollowup::new().add_embed(embed))
+}
@@ -0,0 +1,110 @@
+use log::{info, warn};
+use serenity::all::{
+    AutoArchiveDuration, ChannelId, CommandInteraction, CommandOptionType, CreateCommandOption,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateThread, EditThread, ResolvedOption,
+    ResolvedValue, UserId,
+};
+
+pub fn option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::Boolean, "thread", "Play each quote in its own thread, away from the conversation")
+}
+
+/// Reads the `thread` option, rounds are played in the channel by default.
+pub fn from_options(options: &[ResolvedOption]) -> bool {
+    options.iter().any(|option| matches!(option.value, ResolvedValue::Boolean(true)) && option.name == "thread")
+}
+
+/// Name of the thread a round is played in.
+pub fn name(number: usize, rounds: usize) -> String {
+    if rounds > 1 {
+        format!("Guessquote round {} of {}", number, rounds)
+    } else {
+        "Guessquote".to_string()
+    }
+}
+
+/// Posts the starter message in the channel, as the command reply for the first
+/// round of a game, and opens the round's thread on it. Returns `None` when the
+/// thread couldn't be created, the round is then played in the channel.
+pub async fn open(
+    ctx: &serenity::client::Context,
+    command: &CommandInteraction,
+    as_reply: bool,
+    name: String,
+) -> Result<Option<ChannelId>, serenity::Error> {
+    let starter = "🧵 A new quote is up in the thread below, only answers posted there count!";
+    let starter_id = if as_reply {
+        command
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(starter)),
+            )
+            .await?;
+        command.get_response(&ctx.http).await?.id
+    } else {
+        command.channel_id.say(&ctx.http, starter).await?.id
+    };
+
+    let thread = CreateThread::new(name).auto_archive_duration(AutoArchiveDuration::OneHour);
+    match command.channel_id.create_thread_from_message(&ctx.http, starter_id, thread).await {
+        Ok(thread) => {
+            info!("Opened thread {} for the round in channel {}", thread.id, command.channel_id);
+            Ok(Some(thread.id))
+        }
+        Err(e) => {
+            warn!("Failed to open a round thread in channel {}, playing in the channel: {}", command.channel_id, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Archives the thread once the results are in, a failure only leaves it open.
+pub async fn close(ctx: &serenity::client::Context, thread_id: ChannelId) {
+    if let Err(e) = thread_id.edit_thread(&ctx.http, EditThread::new().archived(true)).await {
+        warn!("Failed to archive round thread {}: {}", thread_id, e);
+    }
+}
+
+/// The line the channel gets once a round in a thread is over.
+pub fn summary(guesses: usize, correct: usize, best: Option<(UserId, i32)>, thread_id: ChannelId) -> String {
+    if guesses == 0 {
+        return format!("⌛ Nobody guessed in time. Results in <#{}>", thread_id);
+    }
+
+    let best = match best {
+        Some((user_id, points)) if points > 0 => format!(", top score <@{}> with +{}", user_id, points),
+        _ => String::new(),
+    };
+    format!("✅ {} of {} guesses were right{}. Results in <#{}>", correct, guesses, best, thread_id)
+}
+
+/// The line the channel gets when a round in a thread is cancelled.
+pub fn cancelled_summary(cancelled_by: UserId, thread_id: ChannelId) -> String {
+    format!("🛑 Round cancelled by <@{}>. Details in <#{}>", cancelled_by, thread_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threads_are_named_after_the_round() {
+        assert_eq!(name(1, 1), "Guessquote");
+        assert_eq!(name(2, 5), "Guessquote round 2 of 5");
+    }
+
+    #[test]
+    fn summary_links_the_thread() {
+        let thread = ChannelId::new(77);
+        assert_eq!(summary(0, 0, None, thread), "⌛ Nobody guessed in time. Results in <#77>");
+        assert_eq!(
+            summary(4, 3, Some((UserId::new(5), 95)), thread),
+            "✅ 3 of 4 guesses were right, top score <@5> with +95. Results in <#77>"
+        );
+        assert_eq!(
+            summary(2, 0, Some((UserId::new(5), -5)), thread),
+            "✅ 0 of 2 guesses were right. Results in <#77>"
+        );
+        assert_eq!(cancelled_summary(UserId::new(5), thread), "🛑 Round cancelled by <@5>. Details in <#77>");
+    }
+}
@@ -0,0 +1,373 @@
+use log::{info, warn};
+use serenity::all::{
+    ComponentInteractionCollector, ComponentInteractionDataKind, CreateActionRow, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, GuildId,
+    MessageId, UserId,
+};
+use serenity::futures::StreamExt;
+use sqlx::{MySql, MySqlPool, Transaction};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::games::{Answers, RoundState};
+use super::history::RoundRecord;
+use super::{ledger, seasons};
+use super::RoundRules;
+
+pub const WAGER_MENU_ID: &str = "guessquote-wager";
+/// Shares of the balance the menu offers, the configured cap leaves out the bigger ones.
+const PERCENTS: [u32; 5] = [10, 25, 50, 75, 100];
+
+/// Points a player put on their answer in a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wager {
+    pub user_id: UserId,
+    /// The player's points the stake was worked out from, when the wager was
+    /// placed and again when it is settled.
+    pub balance: i32,
+    pub percent: u32,
+    pub stake: i32,
+    /// How far into the round the wager was placed.
+    pub placed_after: Duration,
+}
+
+/// How a wager ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    /// The player never answered, answered before wagering, the round was
+    /// cancelled or couldn't be recorded. The stake stays with the player.
+    Void,
+}
+
+impl Outcome {
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Won => "won",
+            Outcome::Lost => "lost",
+            Outcome::Void => "void",
+        }
+    }
+}
+
+/// A wager once the round is over.
+pub struct Settled {
+    pub wager: Wager,
+    pub outcome: Outcome,
+}
+
+impl Settled {
+    /// Points the wager moves: the stake doubles when right and is lost when wrong.
+    pub fn payout(&self) -> i32 {
+        match self.outcome {
+            Outcome::Won => self.wager.stake,
+            Outcome::Lost => -self.wager.stake,
+            Outcome::Void => 0,
+        }
+    }
+}
+
+/// The shares of their points players may stake under these rules.
+fn percents(rules: &RoundRules) -> Vec<u32> {
+    PERCENTS.into_iter().filter(|&percent| percent <= rules.max_wager_percent).collect()
+}
+
+/// The stake for a share of the balance, nothing can be staked from a balance
+/// at or below zero.
+fn stake(balance: i32, percent: u32) -> i32 {
+    (balance.max(0) as i64 * percent as i64 / 100) as i32
+}
+
+/// The select menu players place their wager with, when the rules allow one.
+pub fn menu(rules: &RoundRules) -> Option<CreateActionRow> {
+    let percents = percents(rules);
+    if percents.is_empty() {
+        return None;
+    }
+
+    let options = std::iter::once(CreateSelectMenuOption::new("No wager", "0"))
+        .chain(percents.into_iter().map(|percent| {
+            CreateSelectMenuOption::new(format!("Stake {}% of your points", percent), percent.to_string())
+        }))
+        .collect();
+    let menu = CreateSelectMenu::new(WAGER_MENU_ID, CreateSelectMenuKind::String { options })
+        .placeholder("🎲 Wager some of your points on your answer");
+    Some(CreateActionRow::SelectMenu(menu))
+}
+
+/// Decides a wager from the player's first answer, later answers don't change
+/// it. Wagers only count when they were placed before answering.
+fn outcome(wager: &Wager, answers: Option<Answers>, cancelled: bool) -> Outcome {
+    match answers {
+        _ if cancelled => Outcome::Void,
+        Some(answers) if wager.placed_after < answers.first_after => {
+            if answers.first_correct { Outcome::Won } else { Outcome::Lost }
+        }
+        _ => Outcome::Void,
+    }
+}
+
+/// The round result section listing every wager.
+pub fn summary(settled: &[Settled]) -> String {
+    if settled.is_empty() {
+        return String::new();
+    }
+
+    let mut section = "\n🎲 **Wagers:**\n".to_string();
+    for wager in settled {
+        let result = match wager.outcome {
+            Outcome::Won => format!("won, +{}", wager.payout()),
+            Outcome::Lost => format!("lost, {}", wager.payout()),
+            Outcome::Void => "void, the stake was kept".to_string(),
+        };
+        section.push_str(&format!("<@{}> staked {}: {}\n", wager.wager.user_id, wager.wager.stake, result));
+    }
+    section
+}
+
+async fn balance(db_pool: &MySqlPool, guild_id: GuildId, user_id: UserId) -> Result<i32, sqlx::Error> {
+    let row = sqlx::query_as::<_, (i32,)>("SELECT points FROM wdl_database.quote_scores WHERE guild_id = ? AND user_id = ?")
+        .bind(i64::from(guild_id))
+        .bind(i64::from(user_id))
+        .fetch_optional(db_pool)
+        .await?;
+    Ok(row.map(|(points,)| points).unwrap_or(0))
+}
+
+/// Takes wagers from the menu on the round message until the round ends. A
+/// player's latest choice replaces the earlier one.
+pub async fn serve(
+    ctx: &serenity::client::Context,
+    db_pool: &MySqlPool,
+    round: &RoundState,
+    message_id: MessageId,
+    guild_id: GuildId,
+    start_time: Instant,
+    rules: &RoundRules,
+) -> HashMap<UserId, Wager> {
+    let mut wagers = HashMap::new();
+    let allowed = percents(rules);
+
+    let remaining = rules.duration().saturating_sub(start_time.elapsed());
+    let mut interactions = ComponentInteractionCollector::new(&ctx.shard)
+        .message_id(message_id)
+        .filter(|interaction| interaction.data.custom_id == WAGER_MENU_ID)
+        .timeout(remaining)
+        .stream();
+
+    loop {
+        let interaction = tokio::select! {
+            interaction = interactions.next() => interaction,
+            _ = round.cancelled() => None,
+        };
+        let Some(interaction) = interaction else {
+            break;
+        };
+
+        let percent = match &interaction.data.kind {
+            ComponentInteractionDataKind::StringSelect { values } => values.first().and_then(|value| value.parse::<u32>().ok()),
+            _ => None,
+        };
+        let user_id = interaction.user.id;
+        let reply = match percent {
+            Some(0) => {
+                wagers.remove(&user_id);
+                "No wager for you this round.".to_string()
+            }
+            Some(percent) if allowed.contains(&percent) => match balance(db_pool, guild_id, user_id).await {
+                Ok(balance) if stake(balance, percent) > 0 => {
+                    let wager = Wager {
+                        user_id,
+                        balance,
+                        percent,
+                        stake: stake(balance, percent),
+                        placed_after: start_time.elapsed(),
+                    };
+                    info!("User {} wagered {} of {} points", user_id, wager.stake, balance);
+                    let reply = format!(
+                        "🎲 Staked {}% of your points: **{}**. Doubled if you're right, lost if you're wrong. Only counts if placed before you answer.",
+                        percent, wager.stake
+                    );
+                    wagers.insert(user_id, wager);
+                    reply
+                }
+                Ok(_) => "You have no points to stake yet.".to_string(),
+                Err(e) => {
+                    warn!("Failed to fetch the balance of {} for a wager: {}", user_id, e);
+                    "Your points couldn't be loaded, no wager was placed.".to_string()
+                }
+            },
+            _ => {
+                warn!("Ignoring invalid wager from {}: {:?}", user_id, interaction.data.kind);
+                "That wager isn't allowed.".to_string()
+            }
+        };
+
+        if let Err(e) = interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(reply).ephemeral(true)),
+            )
+            .await
+        {
+            warn!("Error answering wager menu: {}", e);
+        }
+    }
+
+    wagers
+}
+
+/// Settles the wagers of a round against the players' answers, moves the points
+/// and records every wager. Wagers in a round that wasn't recorded are void, as
+/// neither the payouts nor the wagers could be tied to it.
+pub async fn settle(
+    db_pool: &MySqlPool,
+    guild_id: GuildId,
+    round: &RoundState,
+    record: &RoundRecord,
+    wagers: HashMap<UserId, Wager>,
+    cancelled: bool,
+) -> Vec<Settled> {
+    let void = cancelled || !record.recorded;
+    let mut settled: Vec<Settled> = wagers.into_values()
+        .map(|wager| {
+            let outcome = outcome(&wager, round.answers_of(wager.user_id), void);
+            Settled { wager, outcome }
+        })
+        .collect();
+    settled.sort_by_key(|settled| settled.wager.placed_after);
+
+    if !record.recorded {
+        if !settled.is_empty() {
+            warn!("Voided {} wagers, round {} wasn't recorded", settled.len(), record.id);
+        }
+        return settled;
+    }
+
+    let season_id = if settled.iter().any(|wager| wager.payout() != 0) {
+        seasons::current_season(db_pool, guild_id).await.map(|(season_id, _)| season_id)
+    } else {
+        None
+    };
+    for wager in settled.iter_mut() {
+        let booked = async {
+            let mut transaction = db_pool.begin().await?;
+            book(&mut transaction, guild_id, season_id, record, wager).await?;
+            transaction.commit().await
+        }
+        .await;
+        if let Err(e) = booked {
+            // Nothing was written, so the wager didn't move any points either
+            warn!("Failed to settle the wager of {} in round {}: {}", wager.wager.user_id, record.id, e);
+            wager.outcome = Outcome::Void;
+        }
+    }
+
+    settled
+}
+
+/// Books a wager's payout together with its `quote_wagers` row. The stake is
+/// capped against the player's balance as it is now, locked until the
+/// transaction ends, as wagers in other channels may have moved it since.
+async fn book(
+    transaction: &mut Transaction<'_, MySql>,
+    guild_id: GuildId,
+    season_id: Option<i32>,
+    record: &RoundRecord,
+    wager: &mut Settled,
+) -> Result<(), sqlx::Error> {
+    let user_id = i64::from(wager.wager.user_id);
+    if wager.outcome != Outcome::Void {
+        let balance = ledger::fetch_totals(transaction, ledger::Scope::Guild(guild_id), user_id).await?.points;
+        let capped = wager.wager.stake.min(stake(balance, wager.wager.percent));
+        if capped < wager.wager.stake {
+            info!("Capped the stake of {} from {} to {} on a balance of {}", user_id, wager.wager.stake, capped, balance);
+        }
+        wager.wager.balance = balance;
+        wager.wager.stake = capped;
+        if capped == 0 {
+            wager.outcome = Outcome::Void;
+        }
+    }
+
+    let payout = wager.payout();
+    if payout != 0 {
+        ledger::append(transaction, &ledger::Entry::wager(guild_id, user_id, season_id, &record.id, payout)).await?;
+    }
+
+    sqlx::query(
+        "INSERT INTO wdl_database.quote_wagers
+         (round_id, guild_id, user_id, balance, percent, stake, placed_after_ms, outcome, payout)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+        .bind(&record.id)
+        .bind(i64::from(guild_id))
+        .bind(user_id)
+        .bind(wager.wager.balance)
+        .bind(wager.wager.percent)
+        .bind(wager.wager.stake)
+        .bind(wager.wager.placed_after.as_millis() as i64)
+        .bind(wager.outcome.name())
+        .bind(payout)
+        .execute(&mut **transaction)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wager(placed_after_secs: u64) -> Wager {
+        Wager {
+            user_id: UserId::new(1),
+            balance: 400,
+            percent: 25,
+            stake: 100,
+            placed_after: Duration::from_secs(placed_after_secs),
+        }
+    }
+
+    #[test]
+    fn stakes_follow_the_balance_and_cap() {
+        assert_eq!(stake(400, 25), 100);
+        assert_eq!(stake(-50, 50), 0);
+        let rules = RoundRules { max_wager_percent: 50, ..RoundRules::default() };
+        assert_eq!(percents(&rules), vec![10, 25, 50]);
+        assert!(menu(&RoundRules { max_wager_percent: 0, ..RoundRules::default() }).is_none());
+    }
+
+    fn answered(first_after_secs: u64, first_correct: bool) -> Option<Answers> {
+        Some(Answers { first_after: Duration::from_secs(first_after_secs), first_correct, solved: first_correct })
+    }
+
+    #[test]
+    fn wagers_count_only_before_answering() {
+        assert_eq!(outcome(&wager(5), answered(10, true), false), Outcome::Won);
+        assert_eq!(outcome(&wager(5), answered(10, false), false), Outcome::Lost);
+        assert_eq!(outcome(&wager(12), answered(10, true), false), Outcome::Void);
+        assert_eq!(outcome(&wager(5), None, false), Outcome::Void);
+        assert_eq!(outcome(&wager(5), answered(10, true), true), Outcome::Void);
+    }
+
+    #[test]
+    fn wagers_settle_on_the_first_answer() {
+        let round = RoundState::new(UserId::new(9), "finish", Duration::from_secs(30), 1);
+        round.record_guess(UserId::new(1), Duration::from_secs(4), false);
+        round.record_guess(UserId::new(1), Duration::from_secs(9), true);
+        assert_eq!(outcome(&wager(2), round.answers_of(UserId::new(1)), false), Outcome::Lost);
+    }
+
+    #[test]
+    fn payouts_double_or_lose_the_stake() {
+        let settled = [
+            Settled { wager: wager(1), outcome: Outcome::Won },
+            Settled { wager: wager(2), outcome: Outcome::Lost },
+        ];
+        assert_eq!(settled[0].payout(), 100);
+        assert_eq!(settled[1].payout(), -100);
+        assert_eq!(summary(&settled), "\n🎲 **Wagers:**\n<@1> staked 100: won, +100\n<@1> staked 100: lost, -100\n");
+        assert_eq!(summary(&[]), "");
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{Datelike, NaiveDate};
+
+use super::scoring::{self, GuessPoints};
+use super::RoundRules;
+
+/// Share of the points a guess of only the right year is worth.
+const YEAR_ONLY_SHARE: f64 = 0.6;
+/// A month guess this many months off scores nothing.
+const MONTHS_UNTIL_ZERO: f64 = 12.0;
+/// A year guess this many years off scores nothing.
+const YEARS_UNTIL_ZERO: f64 = 2.0;
+
+const MONTH_NAMES: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june",
+    "july", "august", "september", "october", "november", "december",
+];
+
+/// A guess of when a quote was said, either a year or a year and month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhenGuess {
+    pub year: i32,
+    pub month: Option<u32>,
+}
+
+impl WhenGuess {
+    /// A guess counts as right when it has the right year.
+    pub fn is_correct(&self, said_on: NaiveDate) -> bool {
+        self.year == said_on.year()
+    }
+
+    /// Points for how close the guess is, before any streak bonus. An exact month
+    /// is worth `max_points` and an exact year a share of that.
+    pub fn closeness_points(&self, said_on: NaiveDate, rules: &RoundRules) -> i32 {
+        let share = match self.month {
+            Some(month) => {
+                let guessed = self.year * 12 + month as i32 - 1;
+                let actual = said_on.year() * 12 + said_on.month0() as i32;
+                1.0 - (guessed - actual).abs() as f64 / MONTHS_UNTIL_ZERO
+            }
+            None => YEAR_ONLY_SHARE * (1.0 - (self.year - said_on.year()).abs() as f64 / YEARS_UNTIL_ZERO),
+        };
+        (rules.max_points as f64 * share.max(0.0)).round() as i32
+    }
+
+    pub fn describe(&self) -> String {
+        match self.month {
+            Some(month) => format!("{} {}", capitalize(MONTH_NAMES[month as usize - 1]), self.year),
+            None => self.year.to_string(),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+    match characters.next() {
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+        None => String::new(),
+    }
+}
+
+/// Reads a guess like `2023`, `2023-05`, `05/2023` or `May 2023` from a message.
+/// Messages without a plausible year are ordinary chat and give `None`.
+pub fn parse_guess(content: &str) -> Option<WhenGuess> {
+    let tokens: Vec<String> = content
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | '/' | '.' | ','))
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if tokens.is_empty() || tokens.len() > 3 {
+        return None;
+    }
+
+    let (year_index, year) = tokens.iter()
+        .enumerate()
+        .filter(|(_, token)| token.len() == 4)
+        .find_map(|(index, token)| token.parse::<i32>().ok().map(|year| (index, year)))
+        .filter(|(_, year)| (2000..=2100).contains(year))?;
+
+    let month = tokens.iter()
+        .enumerate()
+        .filter(|(index, _)| *index != year_index)
+        .find_map(|(_, token)| match token.parse::<u32>() {
+            Ok(month) => Some(month).filter(|month| (1..=12).contains(month)),
+            Err(_) if token.len() >= 3 => MONTH_NAMES.iter()
+                .position(|name| name.starts_with(token.as_str()))
+                .map(|index| index as u32 + 1),
+            Err(_) => None,
+        });
+
+    Some(WhenGuess { year, month })
+}
+
+/// Scores a guess in a "when" round. Wrong years score by closeness alone, right
+/// ones also earn the streak bonus.
+pub fn score_guess(guess: &WhenGuess, said_on: NaiveDate, current_streak: i32, rules: &RoundRules) -> GuessPoints {
+    GuessPoints {
+        base: guess.closeness_points(said_on, rules),
+        streak_bonus: if guess.is_correct(said_on) { scoring::streak_bonus(current_streak, rules) } else { 0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, 14).unwrap()
+    }
+
+    #[test]
+    fn parses_years_and_months() {
+        assert_eq!(parse_guess("2023"), Some(WhenGuess { year: 2023, month: None }));
+        assert_eq!(parse_guess("2023-05"), Some(WhenGuess { year: 2023, month: Some(5) }));
+        assert_eq!(parse_guess("5/2023"), Some(WhenGuess { year: 2023, month: Some(5) }));
+        assert_eq!(parse_guess("May 2023"), Some(WhenGuess { year: 2023, month: Some(5) }));
+        assert_eq!(parse_guess("sept 2021"), Some(WhenGuess { year: 2021, month: Some(9) }));
+    }
+
+    #[test]
+    fn ignores_chat() {
+        assert_eq!(parse_guess("no idea"), None);
+        assert_eq!(parse_guess("it was 2023 I think, maybe"), None);
+        assert_eq!(parse_guess("1234"), None);
+        assert_eq!(parse_guess("13/2023"), Some(WhenGuess { year: 2023, month: None }));
+    }
+
+    #[test]
+    fn closer_guesses_score_more() {
+        let rules = RoundRules::default();
+        let said_on = date(2023, 5);
+        let exact = WhenGuess { year: 2023, month: Some(5) };
+        let close = WhenGuess { year: 2023, month: Some(8) };
+        let year_only = WhenGuess { year: 2023, month: None };
+        let far = WhenGuess { year: 2021, month: Some(5) };
+
+        assert_eq!(exact.closeness_points(said_on, &rules), 100);
+        assert_eq!(close.closeness_points(said_on, &rules), 75);
+        assert_eq!(year_only.closeness_points(said_on, &rules), 60);
+        assert_eq!(WhenGuess { year: 2022, month: None }.closeness_points(said_on, &rules), 30);
+        assert_eq!(far.closeness_points(said_on, &rules), 0);
+    }
+
+    #[test]
+    fn month_guesses_cross_years() {
+        let rules = RoundRules::default();
+        let guess = WhenGuess { year: 2022, month: Some(12) };
+        assert_eq!(guess.closeness_points(date(2023, 1), &rules), 92);
+        assert!(!guess.is_correct(date(2023, 1)));
+        assert_eq!(guess.describe(), "December 2022");
+    }
+}
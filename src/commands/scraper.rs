@@ -13,12 +13,18 @@ pub async fn scrape_messages(
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     info!("scrape_messages: Starting scrape");
 
+    // Messages fetched over HTTP don't carry their guild, so look it up once
+    let guild_id = match channel_id.to_channel(&ctx.http).await?.guild() {
+        Some(channel) => i64::from(channel.guild_id),
+        None => return Err(format!("Channel {} is not in a guild", channel_id).into()),
+    };
+
     let mut messages = channel_id.messages_iter(&ctx.http).boxed();
 
     while let Some(message) = messages.next().await {
         match message {
             Ok(msg) => {
-                if msg.timestamp > start_date && msg.timestamp < end_date.into() {
+                if msg.timestamp > start_date && msg.timestamp < end_date {
                     // Print the message details
                     info!(
                         "scrape_messages: {}@{}@{}@{}@{}@{}@{:?}",
@@ -37,13 +43,14 @@ pub async fn scrape_messages(
                     // Insert message details into the database
                     let insert_query = "
                         INSERT INTO wdl_database.discord_messages
-                        (MessageId, ChannelId, UserId, Name, Content, Timestamp, PremiumType)
-                        VALUES (?, ?, ?, ?, ?, ?, ?);
+                        (MessageId, GuildId, ChannelId, UserId, Name, Content, Timestamp, PremiumType)
+                        VALUES (?, ?, ?, ?, ?, ?, ?, ?);
                     ";
 
                     // Execute the query
                     if let Err(e) = sqlx::query(insert_query)
                         .bind(i64::from(msg.id))
+                        .bind(guild_id)
                         .bind(i64::from(msg.channel_id))
                         .bind(i64::from(msg.author.id))
                         .bind(msg.author.name)
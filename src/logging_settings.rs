@@ -29,7 +29,6 @@ pub fn setup_loggers() {
 
     let file = OpenOptions::new()
         .create(true) // This will create the file if it does not exist
-        .write(true) // Open the file in write mode
         .append(true) // Set the file to append mode
         .open(filename)
         .expect("failed to open or create log file");
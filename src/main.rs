@@ -1,9 +1,10 @@
 use clap::Parser;
-use log::{info, warn};
-use std::{fs, path::Path, sync::OnceLock};
+use log::{error, info, warn};
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
 use toml::Value;
 use serenity::{
-    all::{ChannelId, Command, CreateCommand},
+    all::{ChannelId, Command, GuildId},
     async_trait,
     model::{channel::Message, gateway::Ready, Timestamp},
     prelude::*,
@@ -22,7 +23,26 @@ mod setup;
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const BUILD_ID: &str = env!("BUILD_ID");
 
-static ALLOWED_QUOTE_USERS: OnceLock<Vec<i64>> = OnceLock::new();
+static ALLOWED_QUOTE_USERS: OnceLock<AllowedQuoteUsers> = OnceLock::new();
+static GUESSQUOTE_RULES: OnceLock<quote::RoundRules> = OnceLock::new();
+static QUOTE_SELECTION: OnceLock<quote::SelectionSettings> = OnceLock::new();
+/// Set by the first READY event. Discord sends READY again after every reconnect,
+/// the one-off startup work must not run twice.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Discord user IDs that can be quoted. Guilds with their own list in the config
+/// use it, every other guild falls back to the top-level `allowed_user_ids`.
+#[derive(Debug, Default)]
+struct AllowedQuoteUsers {
+    default: Vec<i64>,
+    guilds: HashMap<u64, Vec<i64>>,
+}
+
+impl AllowedQuoteUsers {
+    fn for_guild(&self, guild_id: GuildId) -> &[i64] {
+        self.guilds.get(&guild_id.get()).unwrap_or(&self.default)
+    }
+}
 
 fn ensure_config_exists() {
     let config_dir = Path::new("config");
@@ -48,34 +68,104 @@ allowed_user_ids = [
     95565218498748416,
     1092454499236462783,
     243785081167151104,
-]"#;
+]
+
+# Optional per-guild lists that replace allowed_user_ids in that guild
+# [guilds.123456789012345678]
+# allowed_user_ids = [121751619149758464]
+
+# Default rules for /guessquote rounds, players can override some per round
+[guessquote]
+duration_secs = 30
+min_length = 20
+max_points = 100
+min_points = 10
+wrong_guess_penalty = 5
+hardcore_wrong_guess_penalty = 20
+streak_bonus_per_level = 5
+streak_bonus_cap = 25
+hint_cost = 20
+max_wager_percent = 50
+
+# How quotes are picked for /guessquote and the random quotes in chat
+[selection]
+refresh_secs = 600
+recent_window = 100"#;
         fs::write(config_file, default_config).expect("Failed to create default config file");
     }
 }
 
-fn load_allowed_user_ids() -> Vec<i64> {
+fn parse_user_ids(value: &Value) -> Option<Vec<i64>> {
+    value.get("allowed_user_ids")
+        .and_then(|v| v.as_array())
+        .map(|array| array.iter().filter_map(|v| v.as_integer()).collect())
+}
+
+fn load_allowed_user_ids() -> AllowedQuoteUsers {
     match fs::read_to_string("config/quote_settings.toml") {
         Ok(content) => {
             match content.parse::<Value>() {
                 Ok(value) => {
-                    if let Some(array) = value.get("allowed_user_ids").and_then(|v| v.as_array()) {
-                        array.iter()
-                            .filter_map(|v| v.as_integer().map(|i| i as i64))
-                            .collect()
-                    } else {
+                    let default = parse_user_ids(&value).unwrap_or_else(|| {
                         warn!("No allowed_user_ids found in config, using empty list");
                         Vec::new()
+                    });
+
+                    let mut guilds = HashMap::new();
+                    if let Some(table) = value.get("guilds").and_then(|v| v.as_table()) {
+                        for (guild_id, guild_config) in table {
+                            match (guild_id.parse::<u64>(), parse_user_ids(guild_config)) {
+                                (Ok(guild_id), Some(user_ids)) => {
+                                    guilds.insert(guild_id, user_ids);
+                                }
+                                _ => warn!("Ignoring invalid guild section in config: {}", guild_id),
+                            }
+                        }
+                    }
+
+                    AllowedQuoteUsers { default, guilds }
+                }
+                Err(e) => {
+                    warn!("Failed to parse config file: {}", e);
+                    AllowedQuoteUsers::default()
+                }
+            }
+        }
+        Err(e) => {
+            warn!("Failed to read config file: {}", e);
+            AllowedQuoteUsers::default()
+        }
+    }
+}
+
+/// Reads a section of the quote settings, falling back to defaults when it is
+/// missing or invalid.
+fn load_config_section<T: serde::de::DeserializeOwned + Default>(section: &str) -> T {
+    match fs::read_to_string("config/quote_settings.toml") {
+        Ok(content) => {
+            match content.parse::<Value>() {
+                Ok(value) => {
+                    match value.get(section).cloned().map(|table| table.try_into::<T>()) {
+                        Some(Ok(settings)) => settings,
+                        Some(Err(e)) => {
+                            warn!("Invalid [{}] section in config, using defaults: {}", section, e);
+                            T::default()
+                        }
+                        None => {
+                            info!("No [{}] section found in config, using defaults", section);
+                            T::default()
+                        }
                     }
                 }
                 Err(e) => {
                     warn!("Failed to parse config file: {}", e);
-                    Vec::new()
+                    T::default()
                 }
             }
         }
         Err(e) => {
             warn!("Failed to read config file: {}", e);
-            Vec::new()
+            T::default()
         }
     }
 }
@@ -88,6 +178,8 @@ struct Handler {
     scraping: bool,
     start_date: Option<Timestamp>,
     end_date: Option<Timestamp>,
+    games: quote::GameRegistry,
+    quotes: quote::QuotePool,
 }
 
 impl Handler {
@@ -107,6 +199,8 @@ impl Handler {
             scraping,
             start_date,
             end_date,
+            games: quote::GameRegistry::new(),
+            quotes: quote::QuotePool::new(),
         }
     }
 }
@@ -116,9 +210,14 @@ impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, bot: Ready) {
         // Register commands
         let commands = vec![
-            CreateCommand::new("guessquote")
-                .description("Start a game where you have to guess who said a quote"),
+            quote::register_guess(),
             quote::register(),
+            quote::register_aliases(),
+            quote::register_stats(),
+            quote::register_seasons(),
+            quote::register_daily(),
+            quote::register_duel(),
+            quote::register_ledger(),
             version::register(),
             f1::register(),
         ];
@@ -127,6 +226,21 @@ impl EventHandler for Handler {
             .await
             .expect("Failed to create commands");
 
+        if !STARTED.swap(true, Ordering::SeqCst) {
+            // Data from before guild scoping belongs to the guild of the configured channel
+            match self.channel_id.to_channel(&ctx.http).await.map(|channel| channel.guild()) {
+                Ok(Some(channel)) => quote::assign_unscoped_rows(&self.db_pool, channel.guild_id).await,
+                Ok(None) => warn!("Configured channel {} is not in a guild", self.channel_id),
+                Err(e) => warn!("Failed to look up configured channel {}: {:?}", self.channel_id, e),
+            }
+
+            // Keep the cached quote pools up to date
+            self.quotes.spawn_refresh(self.db_pool.clone());
+
+            // Reveal finished daily challenges now and after every midnight
+            quote::spawn_daily_reveals(ctx.clone(), self.db_pool.clone());
+        }
+
         // Clone the context and channel_id for use in the F1 race check task
         let ctx_clone = ctx.clone();
         let channel_id = self.channel_id;
@@ -185,8 +299,32 @@ impl EventHandler for Handler {
         if let serenity::model::application::Interaction::Command(command) = interaction {
             match command.data.name.as_str() {
                 "guessquote" => {
-                    if let Err(e) = quote::guess_quote(ctx, &command, &self.db_pool).await {
-                        warn!("Error handling guessquote command: {:?}", e);
+                    // Run the round in its own task so a panic is logged here and the
+                    // channel is released by the game registry instead of staying locked
+                    let db_pool = self.db_pool.clone();
+                    let games = self.games.clone();
+                    let quotes = self.quotes.clone();
+                    let round = tokio::spawn(async move {
+                        quote::handle_guessquote(ctx, &command, &db_pool, &games, &quotes).await
+                    });
+                    match round.await {
+                        Ok(Err(e)) => warn!("Error handling guessquote command: {:?}", e),
+                        Err(e) if e.is_panic() => error!("guessquote round panicked: {:?}", e),
+                        _ => {}
+                    }
+                }
+                "duel" => {
+                    // Duels run for several rounds, so they get their own task like guessquote
+                    let db_pool = self.db_pool.clone();
+                    let games = self.games.clone();
+                    let quotes = self.quotes.clone();
+                    let duel = tokio::spawn(async move {
+                        quote::handle_duel(ctx, &command, &db_pool, &games, &quotes).await
+                    });
+                    match duel.await {
+                        Ok(Err(e)) => warn!("Error handling duel command: {:?}", e),
+                        Err(e) if e.is_panic() => error!("duel panicked: {:?}", e),
+                        _ => {}
                     }
                 }
                 "scoreboard" => {
@@ -194,6 +332,31 @@ impl EventHandler for Handler {
                         warn!("Error handling scoreboard command: {:?}", e);
                     }
                 }
+                "quotealias" => {
+                    if let Err(e) = quote::handle_aliases(ctx, &command, &self.db_pool).await {
+                        warn!("Error handling quotealias command: {:?}", e);
+                    }
+                }
+                "quotestats" => {
+                    if let Err(e) = quote::handle_stats(ctx, &command, &self.db_pool).await {
+                        warn!("Error handling quotestats command: {:?}", e);
+                    }
+                }
+                "season" => {
+                    if let Err(e) = quote::handle_seasons(ctx, &command, &self.db_pool).await {
+                        warn!("Error handling season command: {:?}", e);
+                    }
+                }
+                "quotepoints" => {
+                    if let Err(e) = quote::handle_ledger(ctx, &command, &self.db_pool).await {
+                        warn!("Error handling quotepoints command: {:?}", e);
+                    }
+                }
+                "daily" => {
+                    if let Err(e) = quote::handle_daily(ctx, &command, &self.db_pool, &self.quotes).await {
+                        warn!("Error handling daily command: {:?}", e);
+                    }
+                }
                 "version" => {
                     if let Err(e) = version::show_version(ctx, &command).await {
                         warn!("Error handling version command: {:?}", e);
@@ -208,24 +371,29 @@ impl EventHandler for Handler {
                     warn!("Unknown command: {}", command.data.name);
                 }
             }
+        } else if let serenity::model::application::Interaction::Component(component) = interaction {
+            // Round buttons are handled by their collectors, only daily challenges outlive a round
+            if component.data.custom_id.starts_with(quote::DAILY_BUTTON_PREFIX) {
+                if let Err(e) = quote::handle_daily_component(ctx, &component, &self.db_pool).await {
+                    warn!("Error handling daily challenge button: {:?}", e);
+                }
+            }
         }
     }
 
     async fn message(&self, ctx: Context, msg: Message) {
         let mut counter = self.counter.lock().await;
 
-        let effective_roll_amount = match self.roll_amount {
-            Some(amount) => amount,
-            None => 15, // Default value
-        };
+        let effective_roll_amount = self.roll_amount.unwrap_or(15); // Default value
 
         if let Err(e) = quote::roll_quote(
             ctx,
             &msg,
             self.channel_id,
-            &mut *counter,
+            &mut counter,
             effective_roll_amount,
             &self.db_pool,
+            &self.quotes,
         )
         .await {
             warn!("Error handling roll quote: {:?}", e);
@@ -239,10 +407,12 @@ impl EventHandler for Handler {
 async fn main() {
     logging_settings::setup_loggers();
     
-    // Ensure config exists and load allowed users
+    // Ensure config exists and load allowed users, round rules and selection settings
     ensure_config_exists();
     let allowed_users = load_allowed_user_ids();
     ALLOWED_QUOTE_USERS.set(allowed_users).expect("Failed to set allowed users");
+    GUESSQUOTE_RULES.set(load_config_section("guessquote")).expect("Failed to set guessquote rules");
+    QUOTE_SELECTION.set(load_config_section("selection")).expect("Failed to set quote selection settings");
     let cli_args: cli::CliCommands = cli::CliCommands::parse();
 
     // Generate a random UUID